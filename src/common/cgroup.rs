@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use crate::common::fs as cfs;
+use crate::context::Context;
+
+const PROC_SELF_CGROUP: &str = "self/cgroup";
+const SYS_CGROUP: &str = "fs/cgroup";
+
+/// Cgroup is the directory of the current process' cgroup for one
+/// controller, on either cgroup hierarchy.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cgroup {
+    V1 { root: PathBuf, dir: PathBuf },
+    V2 { root: PathBuf, dir: PathBuf },
+}
+
+impl Cgroup {
+    pub fn dir(&self) -> &Path {
+        match self {
+            Cgroup::V1 { dir, .. } | Cgroup::V2 { dir, .. } => dir,
+        }
+    }
+
+    /// ancestors returns the cgroup directory and its parents, up to and
+    /// including the root of the hierarchy.
+    pub fn ancestors(&self) -> impl Iterator<Item = &Path> {
+        let (root, dir) = match self {
+            Cgroup::V1 { root, dir } | Cgroup::V2 { root, dir } => (root, dir),
+        };
+        dir.ancestors().take_while(move |x| x.starts_with(root))
+    }
+}
+
+/// cgroup_of finds the cgroup of the current process for `controller`, e.g.
+/// "cpu" or "cpuset", from /proc/self/cgroup. A cgroup v1 hierarchy with the
+/// controller wins over the unified hierarchy, as on hybrid systems. The
+/// hierarchies are assumed to be mounted at /sys/fs/cgroup.
+pub fn cgroup_of(ctx: &Context, controller: &str) -> Option<Cgroup> {
+    let lines = cfs::read_lines(ctx.proc_path(PROC_SELF_CGROUP)).ok()?;
+    let mut unified = None;
+
+    for line in lines {
+        // hierarchy-ID:controller-list:cgroup-path
+        let fields = line.splitn(3, ':').collect::<Vec<&str>>();
+        if fields.len() < 3 {
+            continue;
+        }
+        let (id, controllers, path) = (fields[0], fields[1], fields[2]);
+
+        if id == "0" && controllers.is_empty() {
+            unified = Some(path.to_string());
+            continue;
+        }
+
+        if controllers.split(',').any(|x| x == controller) {
+            let root = ctx.sys_path(SYS_CGROUP).join(controllers);
+            let root = if root.exists() { root } else { ctx.sys_path(SYS_CGROUP).join(controller) };
+            if root.exists() {
+                let dir = resolve(&root, path);
+                return Some(Cgroup::V1 { root, dir });
+            }
+        }
+    }
+
+    let root = ctx.sys_path(SYS_CGROUP);
+    if !root.join("cgroup.controllers").exists() {
+        return None;
+    }
+    let dir = resolve(&root, &unified?);
+    Some(Cgroup::V2 { root, dir })
+}
+
+/// resolve joins the cgroup path of /proc/self/cgroup to the hierarchy root.
+/// Without a cgroup namespace a container sees its full path there while only
+/// its own cgroup is mounted, so a path that does not exist maps to the root.
+fn resolve(root: &Path, path: &str) -> PathBuf {
+    let dir = root.join(path.trim_start_matches('/'));
+    if dir.exists() { dir } else { root.to_path_buf() }
+}
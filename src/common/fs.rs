@@ -1,15 +1,17 @@
 use std::fs::File;
-use std::io;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+use crate::error::{Error, Result};
+
 /// read_lines_offset_n reads contents from file and splits them by new line.
 /// The offset tells at which line number to start.
 /// The count determines the number of lines to read (starting from offset):
 /// n >= 0: at most n lines
 /// n < 0: whole file
-pub fn read_lines_offset_n(filename: &str, offset: usize, n: isize) -> io::Result<Vec<String>> {
-    let file = File::open(filename)?;
+pub fn read_lines_offset_n<P: AsRef<Path>>(filename: P, offset: usize, n: isize) -> Result<Vec<String>> {
+    let filename = filename.as_ref();
+    let file = File::open(filename).map_err(|e| Error::io(filename, e))?;
     let reader = BufReader::new(file);
     let mut ret = Vec::new();
 
@@ -18,11 +20,11 @@ pub fn read_lines_offset_n(filename: &str, offset: usize, n: isize) -> io::Resul
             continue;
         }
 
-        if n >= 0 && i > (n as usize + offset) {
+        if n >= 0 && i >= (n as usize + offset) {
             break;
         }
 
-        let line = line?;
+        let line = line.map_err(|e| Error::io(filename, e))?;
         ret.push(line);
     };
 
@@ -30,8 +32,15 @@ pub fn read_lines_offset_n(filename: &str, offset: usize, n: isize) -> io::Resul
 }
 
 /// read_lines reads contents from a file and splits them by new lines.
-pub fn read_lines<P: AsRef<Path>>(filename: P) -> io::Result<Vec<String>> {
-    let file = File::open(filename)?;
-    let lines = BufReader::new(file).lines().map(|x| { x.unwrap_or("".to_string()) }).collect();
-    Ok(lines)
-}
\ No newline at end of file
+pub fn read_lines<P: AsRef<Path>>(filename: P) -> Result<Vec<String>> {
+    let filename = filename.as_ref();
+    let file = File::open(filename).map_err(|e| Error::io(filename, e))?;
+    BufReader::new(file).lines().map(|l| l.map_err(|e| Error::io(filename, e))).collect()
+}
+
+/// read_line reads the first line of a file, trimmed, as found in
+/// single-value sysfs attributes.
+pub fn read_line<P: AsRef<Path>>(filename: P) -> Result<String> {
+    let lines = read_lines_offset_n(filename, 0, 1)?;
+    Ok(lines.first().map(|x| x.trim().to_string()).unwrap_or_default())
+}
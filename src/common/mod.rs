@@ -1,2 +1,8 @@
+#[cfg(target_os = "windows")]
 pub mod binary;
-pub mod fs;
\ No newline at end of file
+#[cfg(target_os = "linux")]
+pub mod cgroup;
+pub mod fs;
+pub mod parse;
+#[cfg(unix)]
+pub mod sys;
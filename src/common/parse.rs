@@ -0,0 +1,16 @@
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// parse_field parses `value`, reporting failures as `Error::Parse` against
+/// the file and line it was read from.
+pub fn parse_field<T, P>(value: &str, path: P, line: usize, field: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+    P: AsRef<Path>,
+{
+    value.parse::<T>().map_err(|e| Error::parse(path, line, field, e))
+}
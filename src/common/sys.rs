@@ -0,0 +1,16 @@
+use std::sync::OnceLock;
+
+/// The value Linux has historically used for USER_HZ, assumed if sysconf fails.
+const DEFAULT_CLOCK_TICKS: u64 = 100;
+
+/// clock_ticks returns the number of clock ticks per second (USER_HZ) the
+/// kernel uses for the tick counters in /proc, such as the cpu lines of
+/// /proc/stat. It is queried once through `sysconf(_SC_CLK_TCK)` and cached.
+pub fn clock_ticks() -> u64 {
+    static CLOCK_TICKS: OnceLock<u64> = OnceLock::new();
+
+    *CLOCK_TICKS.get_or_init(|| {
+        let ret = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ret > 0 { ret as u64 } else { DEFAULT_CLOCK_TICKS }
+    })
+}
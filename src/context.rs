@@ -0,0 +1,131 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Context tells the Linux collectors where the host's `/proc`, `/sys`, `/etc`,
+/// `/dev`, `/run` and `/` are mounted.
+///
+/// `Context::default()` honours the `HOST_PROC`, `HOST_SYS`, `HOST_ETC`,
+/// `HOST_DEV`, `HOST_RUN` and `HOST_ROOT` environment variables, so an agent running in a
+/// container with the host's `/proc` bind-mounted at `/host/proc` only needs
+/// `HOST_PROC=/host/proc`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Context {
+    proc: PathBuf,
+    sys: PathBuf,
+    etc: PathBuf,
+    dev: PathBuf,
+    run: PathBuf,
+    root: PathBuf,
+}
+
+impl Context {
+    /// new returns a context reading from the standard locations,
+    /// ignoring the environment.
+    pub fn new() -> Context {
+        Context {
+            proc: PathBuf::from("/proc"),
+            sys: PathBuf::from("/sys"),
+            etc: PathBuf::from("/etc"),
+            dev: PathBuf::from("/dev"),
+            run: PathBuf::from("/run"),
+            root: PathBuf::from("/"),
+        }
+    }
+
+    /// from_env returns a context whose roots are taken from the `HOST_*`
+    /// environment variables, falling back to the standard locations.
+    pub fn from_env() -> Context {
+        let var = |key: &str, default: &str| {
+            env::var_os(key).filter(|v| !v.is_empty()).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(default))
+        };
+
+        Context {
+            proc: var("HOST_PROC", "/proc"),
+            sys: var("HOST_SYS", "/sys"),
+            etc: var("HOST_ETC", "/etc"),
+            dev: var("HOST_DEV", "/dev"),
+            run: var("HOST_RUN", "/run"),
+            root: var("HOST_ROOT", "/"),
+        }
+    }
+
+    /// from_root returns a context with every root placed under `dir`,
+    /// e.g. `dir/proc` and `dir/sys`. Useful to read a captured tree.
+    pub fn from_root<P: AsRef<Path>>(dir: P) -> Context {
+        let dir = dir.as_ref();
+        Context {
+            proc: dir.join("proc"),
+            sys: dir.join("sys"),
+            etc: dir.join("etc"),
+            dev: dir.join("dev"),
+            run: dir.join("run"),
+            root: dir.to_path_buf(),
+        }
+    }
+
+    pub fn with_proc<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.proc = path.into();
+        self
+    }
+
+    pub fn with_sys<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.sys = path.into();
+        self
+    }
+
+    pub fn with_etc<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.etc = path.into();
+        self
+    }
+
+    pub fn with_dev<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.dev = path.into();
+        self
+    }
+
+    pub fn with_run<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.run = path.into();
+        self
+    }
+
+    pub fn with_root<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.root = path.into();
+        self
+    }
+
+    /// proc_path resolves `path`, relative to `/proc`, against the proc root.
+    pub fn proc_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.proc.join(path)
+    }
+
+    /// sys_path resolves `path`, relative to `/sys`, against the sys root.
+    pub fn sys_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.sys.join(path)
+    }
+
+    /// etc_path resolves `path`, relative to `/etc`, against the etc root.
+    pub fn etc_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.etc.join(path)
+    }
+
+    /// dev_path resolves `path`, relative to `/dev`, against the dev root.
+    pub fn dev_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.dev.join(path)
+    }
+
+    /// run_path resolves `path`, relative to `/run`, against the run root.
+    pub fn run_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.run.join(path)
+    }
+
+    /// root_path resolves `path`, relative to `/`, against the root.
+    pub fn root_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl Default for Context {
+    fn default() -> Context {
+        Context::from_env()
+    }
+}
@@ -0,0 +1,316 @@
+use core::arch::x86_64::{CpuidResult, __cpuid_count};
+use std::collections::HashMap;
+
+use crate::cpu::{CoreType, InfoStat};
+
+#[derive(Clone, Copy)]
+enum Reg {
+    Eax,
+    Ebx,
+    Ecx,
+    Edx,
+}
+
+/// FEATURES maps CPUID bits to the flag names /proc/cpuinfo uses for them,
+/// as (leaf, subleaf, register, bit, name).
+const FEATURES: &[(u32, u32, Reg, u32, &str)] = &[
+    (0x1, 0, Reg::Edx, 0, "fpu"),
+    (0x1, 0, Reg::Edx, 1, "vme"),
+    (0x1, 0, Reg::Edx, 2, "de"),
+    (0x1, 0, Reg::Edx, 3, "pse"),
+    (0x1, 0, Reg::Edx, 4, "tsc"),
+    (0x1, 0, Reg::Edx, 5, "msr"),
+    (0x1, 0, Reg::Edx, 6, "pae"),
+    (0x1, 0, Reg::Edx, 7, "mce"),
+    (0x1, 0, Reg::Edx, 8, "cx8"),
+    (0x1, 0, Reg::Edx, 9, "apic"),
+    (0x1, 0, Reg::Edx, 11, "sep"),
+    (0x1, 0, Reg::Edx, 12, "mtrr"),
+    (0x1, 0, Reg::Edx, 13, "pge"),
+    (0x1, 0, Reg::Edx, 14, "mca"),
+    (0x1, 0, Reg::Edx, 15, "cmov"),
+    (0x1, 0, Reg::Edx, 16, "pat"),
+    (0x1, 0, Reg::Edx, 17, "pse36"),
+    (0x1, 0, Reg::Edx, 18, "pn"),
+    (0x1, 0, Reg::Edx, 19, "clflush"),
+    (0x1, 0, Reg::Edx, 21, "dts"),
+    (0x1, 0, Reg::Edx, 22, "acpi"),
+    (0x1, 0, Reg::Edx, 23, "mmx"),
+    (0x1, 0, Reg::Edx, 24, "fxsr"),
+    (0x1, 0, Reg::Edx, 25, "sse"),
+    (0x1, 0, Reg::Edx, 26, "sse2"),
+    (0x1, 0, Reg::Edx, 27, "ss"),
+    (0x1, 0, Reg::Edx, 28, "ht"),
+    (0x1, 0, Reg::Edx, 29, "tm"),
+    (0x1, 0, Reg::Edx, 30, "ia64"),
+    (0x1, 0, Reg::Edx, 31, "pbe"),
+    (0x80000001, 0, Reg::Edx, 11, "syscall"),
+    (0x80000001, 0, Reg::Edx, 20, "nx"),
+    (0x80000001, 0, Reg::Edx, 22, "mmxext"),
+    (0x80000001, 0, Reg::Edx, 25, "fxsr_opt"),
+    (0x80000001, 0, Reg::Edx, 26, "pdpe1gb"),
+    (0x80000001, 0, Reg::Edx, 27, "rdtscp"),
+    (0x80000001, 0, Reg::Edx, 29, "lm"),
+    (0x80000001, 0, Reg::Edx, 30, "3dnowext"),
+    (0x80000001, 0, Reg::Edx, 31, "3dnow"),
+    (0x1, 0, Reg::Ecx, 0, "pni"),
+    (0x1, 0, Reg::Ecx, 1, "pclmulqdq"),
+    (0x1, 0, Reg::Ecx, 2, "dtes64"),
+    (0x1, 0, Reg::Ecx, 3, "monitor"),
+    (0x1, 0, Reg::Ecx, 4, "ds_cpl"),
+    (0x1, 0, Reg::Ecx, 5, "vmx"),
+    (0x1, 0, Reg::Ecx, 6, "smx"),
+    (0x1, 0, Reg::Ecx, 7, "est"),
+    (0x1, 0, Reg::Ecx, 8, "tm2"),
+    (0x1, 0, Reg::Ecx, 9, "ssse3"),
+    (0x1, 0, Reg::Ecx, 10, "cid"),
+    (0x1, 0, Reg::Ecx, 11, "sdbg"),
+    (0x1, 0, Reg::Ecx, 12, "fma"),
+    (0x1, 0, Reg::Ecx, 13, "cx16"),
+    (0x1, 0, Reg::Ecx, 14, "xtpr"),
+    (0x1, 0, Reg::Ecx, 15, "pdcm"),
+    (0x1, 0, Reg::Ecx, 17, "pcid"),
+    (0x1, 0, Reg::Ecx, 18, "dca"),
+    (0x1, 0, Reg::Ecx, 19, "sse4_1"),
+    (0x1, 0, Reg::Ecx, 20, "sse4_2"),
+    (0x1, 0, Reg::Ecx, 21, "x2apic"),
+    (0x1, 0, Reg::Ecx, 22, "movbe"),
+    (0x1, 0, Reg::Ecx, 23, "popcnt"),
+    (0x1, 0, Reg::Ecx, 24, "tsc_deadline_timer"),
+    (0x1, 0, Reg::Ecx, 25, "aes"),
+    (0x1, 0, Reg::Ecx, 26, "xsave"),
+    (0x1, 0, Reg::Ecx, 28, "avx"),
+    (0x1, 0, Reg::Ecx, 29, "f16c"),
+    (0x1, 0, Reg::Ecx, 30, "rdrand"),
+    (0x1, 0, Reg::Ecx, 31, "hypervisor"),
+    (0x80000001, 0, Reg::Ecx, 0, "lahf_lm"),
+    (0x80000001, 0, Reg::Ecx, 1, "cmp_legacy"),
+    (0x80000001, 0, Reg::Ecx, 2, "svm"),
+    (0x80000001, 0, Reg::Ecx, 3, "extapic"),
+    (0x80000001, 0, Reg::Ecx, 4, "cr8_legacy"),
+    (0x80000001, 0, Reg::Ecx, 5, "abm"),
+    (0x80000001, 0, Reg::Ecx, 6, "sse4a"),
+    (0x80000001, 0, Reg::Ecx, 7, "misalignsse"),
+    (0x80000001, 0, Reg::Ecx, 8, "3dnowprefetch"),
+    (0x80000001, 0, Reg::Ecx, 9, "osvw"),
+    (0x80000001, 0, Reg::Ecx, 10, "ibs"),
+    (0x80000001, 0, Reg::Ecx, 11, "xop"),
+    (0x80000001, 0, Reg::Ecx, 12, "skinit"),
+    (0x80000001, 0, Reg::Ecx, 13, "wdt"),
+    (0x80000001, 0, Reg::Ecx, 15, "lwp"),
+    (0x80000001, 0, Reg::Ecx, 16, "fma4"),
+    (0x80000001, 0, Reg::Ecx, 17, "tce"),
+    (0x80000001, 0, Reg::Ecx, 19, "nodeid_msr"),
+    (0x80000001, 0, Reg::Ecx, 21, "tbm"),
+    (0x80000001, 0, Reg::Ecx, 22, "topoext"),
+    (0x80000001, 0, Reg::Ecx, 23, "perfctr_core"),
+    (0x80000001, 0, Reg::Ecx, 24, "perfctr_nb"),
+    (0x80000001, 0, Reg::Ecx, 26, "bpext"),
+    (0x80000001, 0, Reg::Ecx, 27, "ptsc"),
+    (0x80000001, 0, Reg::Ecx, 28, "perfctr_llc"),
+    (0x80000001, 0, Reg::Ecx, 29, "mwaitx"),
+    (0x7, 0, Reg::Ebx, 0, "fsgsbase"),
+    (0x7, 0, Reg::Ebx, 1, "tsc_adjust"),
+    (0x7, 0, Reg::Ebx, 2, "sgx"),
+    (0x7, 0, Reg::Ebx, 3, "bmi1"),
+    (0x7, 0, Reg::Ebx, 4, "hle"),
+    (0x7, 0, Reg::Ebx, 5, "avx2"),
+    (0x7, 0, Reg::Ebx, 7, "smep"),
+    (0x7, 0, Reg::Ebx, 8, "bmi2"),
+    (0x7, 0, Reg::Ebx, 9, "erms"),
+    (0x7, 0, Reg::Ebx, 10, "invpcid"),
+    (0x7, 0, Reg::Ebx, 11, "rtm"),
+    (0x7, 0, Reg::Ebx, 12, "cqm"),
+    (0x7, 0, Reg::Ebx, 14, "mpx"),
+    (0x7, 0, Reg::Ebx, 15, "rdt_a"),
+    (0x7, 0, Reg::Ebx, 16, "avx512f"),
+    (0x7, 0, Reg::Ebx, 17, "avx512dq"),
+    (0x7, 0, Reg::Ebx, 18, "rdseed"),
+    (0x7, 0, Reg::Ebx, 19, "adx"),
+    (0x7, 0, Reg::Ebx, 20, "smap"),
+    (0x7, 0, Reg::Ebx, 21, "avx512ifma"),
+    (0x7, 0, Reg::Ebx, 23, "clflushopt"),
+    (0x7, 0, Reg::Ebx, 24, "clwb"),
+    (0x7, 0, Reg::Ebx, 25, "intel_pt"),
+    (0x7, 0, Reg::Ebx, 26, "avx512pf"),
+    (0x7, 0, Reg::Ebx, 27, "avx512er"),
+    (0x7, 0, Reg::Ebx, 28, "avx512cd"),
+    (0x7, 0, Reg::Ebx, 29, "sha_ni"),
+    (0x7, 0, Reg::Ebx, 30, "avx512bw"),
+    (0x7, 0, Reg::Ebx, 31, "avx512vl"),
+    (0xd, 1, Reg::Eax, 0, "xsaveopt"),
+    (0xd, 1, Reg::Eax, 1, "xsavec"),
+    (0xd, 1, Reg::Eax, 2, "xgetbv1"),
+    (0xd, 1, Reg::Eax, 3, "xsaves"),
+    (0x7, 1, Reg::Eax, 4, "avx_vnni"),
+    (0x7, 1, Reg::Eax, 5, "avx512_bf16"),
+    (0x7, 0, Reg::Ecx, 1, "avx512vbmi"),
+    (0x7, 0, Reg::Ecx, 2, "umip"),
+    (0x7, 0, Reg::Ecx, 3, "pku"),
+    (0x7, 0, Reg::Ecx, 4, "ospke"),
+    (0x7, 0, Reg::Ecx, 5, "waitpkg"),
+    (0x7, 0, Reg::Ecx, 6, "avx512_vbmi2"),
+    (0x7, 0, Reg::Ecx, 8, "gfni"),
+    (0x7, 0, Reg::Ecx, 9, "vaes"),
+    (0x7, 0, Reg::Ecx, 10, "vpclmulqdq"),
+    (0x7, 0, Reg::Ecx, 11, "avx512_vnni"),
+    (0x7, 0, Reg::Ecx, 12, "avx512_bitalg"),
+    (0x7, 0, Reg::Ecx, 13, "tme"),
+    (0x7, 0, Reg::Ecx, 14, "avx512_vpopcntdq"),
+    (0x7, 0, Reg::Ecx, 16, "la57"),
+    (0x7, 0, Reg::Ecx, 22, "rdpid"),
+    (0x7, 0, Reg::Ecx, 25, "cldemote"),
+    (0x7, 0, Reg::Ecx, 27, "movdiri"),
+    (0x7, 0, Reg::Ecx, 28, "movdir64b"),
+    (0x7, 0, Reg::Ecx, 29, "enqcmd"),
+    (0x7, 0, Reg::Edx, 2, "avx512_4vnniw"),
+    (0x7, 0, Reg::Edx, 3, "avx512_4fmaps"),
+    (0x7, 0, Reg::Edx, 4, "fsrm"),
+    (0x7, 0, Reg::Edx, 8, "avx512_vp2intersect"),
+    (0x7, 0, Reg::Edx, 10, "md_clear"),
+    (0x7, 0, Reg::Edx, 14, "serialize"),
+    (0x7, 0, Reg::Edx, 15, "hybrid_cpu"),
+    (0x7, 0, Reg::Edx, 16, "tsxldtrk"),
+    (0x7, 0, Reg::Edx, 18, "pconfig"),
+    (0x7, 0, Reg::Edx, 19, "arch_lbr"),
+    (0x7, 0, Reg::Edx, 22, "amx_bf16"),
+    (0x7, 0, Reg::Edx, 23, "avx512_fp16"),
+    (0x7, 0, Reg::Edx, 24, "amx_tile"),
+    (0x7, 0, Reg::Edx, 25, "amx_int8"),
+    (0x7, 0, Reg::Edx, 27, "stibp"),
+    (0x7, 0, Reg::Edx, 28, "flush_l1d"),
+    (0x7, 0, Reg::Edx, 29, "arch_capabilities"),
+    (0x7, 0, Reg::Edx, 31, "ssbd"),
+];
+
+/// cpuid executes the CPUID instruction. It is safe in recent Rust
+/// versions, but was declared unsafe before.
+#[allow(unused_unsafe)]
+fn cpuid(leaf: u32, subleaf: u32) -> CpuidResult {
+    unsafe { __cpuid_count(leaf, subleaf) }
+}
+
+/// cpuid_info decodes CPUID on the CPU the calling thread is running on.
+/// `cpu` is only used to number the result.
+pub fn cpuid_info(cpu: i32) -> InfoStat {
+    let leaf0 = cpuid(0, 0);
+    let max_leaf = leaf0.eax;
+    let max_ext_leaf = cpuid(0x80000000, 0).eax;
+    let supported = |leaf: u32| if leaf >= 0x80000000 { leaf <= max_ext_leaf } else { leaf <= max_leaf };
+
+    let vendor_id = [leaf0.ebx, leaf0.edx, leaf0.ecx].iter()
+        .flat_map(|x| x.to_le_bytes())
+        .map(char::from)
+        .collect::<String>();
+
+    // the displayed family and model include the extended fields, see
+    // "CPUID—CPU Identification" in the Intel SDM
+    let signature = cpuid(1, 0).eax;
+    let mut family = (signature >> 8) & 0xf;
+    let mut model = (signature >> 4) & 0xf;
+    if family == 0xf {
+        family += (signature >> 20) & 0xff;
+    }
+    if family == 0x6 || family >= 0xf {
+        model += ((signature >> 16) & 0xf) << 4;
+    }
+
+    let mut ret = InfoStat {
+        cpu,
+        vendor_id,
+        family: family.to_string(),
+        model: model.to_string(),
+        stepping: (signature & 0xf) as i32,
+        cores: 1,
+        model_name: brand_string(max_ext_leaf),
+        ..Default::default()
+    };
+
+    // leaf 0xb describes the x2APIC ID layout: SMT bits, then core bits,
+    // then the package
+    if supported(0xb) && cpuid(0xb, 0).ebx != 0 {
+        let smt = cpuid(0xb, 0);
+        let core = cpuid(0xb, 1);
+        let (smt_shift, core_shift) = (smt.eax & 0x1f, core.eax & 0x1f);
+        let apic_id = smt.edx;
+        if core_shift >= smt_shift && core_shift < 32 {
+            ret.physical_id = (apic_id >> core_shift).to_string();
+            ret.core_id = ((apic_id & ((1 << core_shift) - 1)) >> smt_shift).to_string();
+        }
+    }
+
+    // base frequency
+    if supported(0x16) {
+        ret.mhz = (cpuid(0x16, 0).eax & 0xffff) as f64;
+    }
+
+    ret.cache_size = last_level_cache_kb(&ret.vendor_id, max_leaf, max_ext_leaf);
+
+    // CPUID serializes the pipeline, run it once per leaf rather than per bit
+    let mut leaves: HashMap<(u32, u32), CpuidResult> = HashMap::new();
+    ret.flags = FEATURES.iter().filter(|(leaf, subleaf, reg, bit, _)| {
+        if !supported(*leaf) {
+            return false;
+        }
+        let r = *leaves.entry((*leaf, *subleaf)).or_insert_with(|| cpuid(*leaf, *subleaf));
+        let v = match reg {
+            Reg::Eax => r.eax,
+            Reg::Ebx => r.ebx,
+            Reg::Ecx => r.ecx,
+            Reg::Edx => r.edx,
+        };
+        v & (1 << bit) != 0
+    }).map(|(.., name)| *name).collect();
+
+    // hybrid parts (Alder Lake and later) tell the core type in leaf 0x1a
+    let hybrid = supported(0x7) && cpuid(0x7, 0).edx & (1 << 15) != 0;
+    if hybrid && supported(0x1a) {
+        ret.core_type = match cpuid(0x1a, 0).eax >> 24 {
+            0x20 => CoreType::Efficiency,
+            0x40 => CoreType::Performance,
+            _ => CoreType::Unknown,
+        };
+    }
+
+    ret
+}
+
+/// brand_string returns the processor name of leaves 0x80000002-0x80000004,
+/// e.g. "Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz".
+fn brand_string(max_ext_leaf: u32) -> String {
+    if max_ext_leaf < 0x80000004 {
+        return String::new();
+    }
+
+    let bytes = (0x80000002..=0x80000004).flat_map(|leaf| {
+        let r = cpuid(leaf, 0);
+        [r.eax, r.ebx, r.ecx, r.edx]
+    }).flat_map(|x| x.to_le_bytes()).take_while(|x| *x != 0).collect::<Vec<u8>>();
+    String::from_utf8_lossy(&bytes).trim().to_string()
+}
+
+/// last_level_cache_kb returns the size of the largest cache in KB, which is
+/// what /proc/cpuinfo reports as "cache size".
+fn last_level_cache_kb(vendor_id: &str, max_leaf: u32, max_ext_leaf: u32) -> i32 {
+    // Intel describes its caches in leaf 4, AMD the same way in 0x8000001d
+    let leaf = match vendor_id {
+        "GenuineIntel" if max_leaf >= 0x4 => 0x4,
+        "AuthenticAMD" | "HygonGenuine" if max_ext_leaf >= 0x8000001d => 0x8000001d,
+        _ => return 0,
+    };
+
+    let mut ret = 0;
+    for subleaf in 0..16 {
+        let r = cpuid(leaf, subleaf);
+        // cache type 0 terminates the list
+        if r.eax & 0x1f == 0 {
+            break;
+        }
+        let ways = (r.ebx >> 22) as u64 + 1;
+        let partitions = ((r.ebx >> 12) & 0x3ff) as u64 + 1;
+        let line_size = (r.ebx & 0xfff) as u64 + 1;
+        let sets = r.ecx as u64 + 1;
+        ret = ret.max(ways * partitions * line_size * sets / 1024);
+    }
+    ret as i32
+}
@@ -0,0 +1,139 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// CpuSet is a set of CPU numbers. It parses from and formats to the kernel's
+/// cpu list format, e.g. `0-3,8-11`, as found in /sys/devices/system/cpu/online
+/// or the `Cpus_allowed_list` of /proc/<pid>/status.
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CpuSet(BTreeSet<u32>);
+
+impl CpuSet {
+    pub fn new() -> CpuSet {
+        CpuSet(BTreeSet::new())
+    }
+
+    pub fn contains(&self, cpu: u32) -> bool {
+        self.0.contains(&cpu)
+    }
+
+    pub fn insert(&mut self, cpu: u32) -> bool {
+        self.0.insert(cpu)
+    }
+
+    pub fn remove(&mut self, cpu: u32) -> bool {
+        self.0.remove(&cpu)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// iter returns the CPUs in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn union(&self, other: &CpuSet) -> CpuSet {
+        CpuSet(self.0.union(&other.0).copied().collect())
+    }
+
+    pub fn intersection(&self, other: &CpuSet) -> CpuSet {
+        CpuSet(self.0.intersection(&other.0).copied().collect())
+    }
+
+    pub fn difference(&self, other: &CpuSet) -> CpuSet {
+        CpuSet(self.0.difference(&other.0).copied().collect())
+    }
+}
+
+/// ParseCpuSetError is returned when a string is not in cpu list format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCpuSetError(String);
+
+impl fmt::Display for ParseCpuSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cpu list: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCpuSetError {}
+
+impl FromStr for CpuSet {
+    type Err = ParseCpuSetError;
+
+    fn from_str(s: &str) -> Result<CpuSet, ParseCpuSetError> {
+        let mut ret = CpuSet::new();
+        let cpu = |v: &str| v.trim().parse::<u32>().map_err(|_| ParseCpuSetError(s.to_string()));
+
+        for range in s.trim().split(',').filter(|x| !x.trim().is_empty()) {
+            match range.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (cpu(start)?, cpu(end)?);
+                    if start > end {
+                        return Err(ParseCpuSetError(s.to_string()));
+                    }
+                    ret.0.extend(start..=end);
+                }
+                None => {
+                    ret.0.insert(cpu(range)?);
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
+impl fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cpus = self.iter().peekable();
+        let mut first = true;
+
+        while let Some(start) = cpus.next() {
+            let mut end = start;
+            while cpus.peek() == Some(&(end + 1)) {
+                end = cpus.next().unwrap();
+            }
+
+            if !first {
+                f.write_str(",")?;
+            }
+            first = false;
+
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromIterator<u32> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> CpuSet {
+        CpuSet(iter.into_iter().collect())
+    }
+}
+
+impl Extend<u32> for CpuSet {
+    fn extend<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for CpuSet {
+    type Item = u32;
+    type IntoIter = btree_set::IntoIter<u32>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+macro_rules! cpu_flags {
+    ($($variant:ident => $name:literal,)*) => {
+        /// CpuFlag is a well-known CPU feature, named as in the "flags" (x86),
+        /// "Features" (ARM, LoongArch) or "isa" (RISC-V) line of /proc/cpuinfo.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[non_exhaustive]
+        pub enum CpuFlag {
+            $($variant,)*
+        }
+
+        impl CpuFlag {
+            /// ALL lists every well-known flag.
+            pub const ALL: &'static [CpuFlag] = &[$(CpuFlag::$variant,)*];
+
+            /// name returns the name the kernel uses for the flag.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(CpuFlag::$variant => $name,)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<CpuFlag> {
+                match name {
+                    $($name => Some(CpuFlag::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+cpu_flags! {
+    // x86
+    Fpu => "fpu",
+    Tsc => "tsc",
+    Msr => "msr",
+    Pae => "pae",
+    Cx8 => "cx8",
+    Apic => "apic",
+    Cmov => "cmov",
+    Clflush => "clflush",
+    Mmx => "mmx",
+    Fxsr => "fxsr",
+    Sse => "sse",
+    Sse2 => "sse2",
+    Ht => "ht",
+    Syscall => "syscall",
+    Nx => "nx",
+    Pdpe1gb => "pdpe1gb",
+    Rdtscp => "rdtscp",
+    Lm => "lm",
+    ConstantTsc => "constant_tsc",
+    NonstopTsc => "nonstop_tsc",
+    Sse3 => "pni",
+    Pclmulqdq => "pclmulqdq",
+    Vmx => "vmx",
+    Ssse3 => "ssse3",
+    Fma => "fma",
+    Cx16 => "cx16",
+    Sse4_1 => "sse4_1",
+    Sse4_2 => "sse4_2",
+    X2apic => "x2apic",
+    Movbe => "movbe",
+    Popcnt => "popcnt",
+    Xsave => "xsave",
+    Avx => "avx",
+    F16c => "f16c",
+    Rdrand => "rdrand",
+    Hypervisor => "hypervisor",
+    LahfLm => "lahf_lm",
+    Svm => "svm",
+    Abm => "abm",
+    Sse4a => "sse4a",
+    Bmi1 => "bmi1",
+    Hle => "hle",
+    Avx2 => "avx2",
+    Smep => "smep",
+    Bmi2 => "bmi2",
+    Erms => "erms",
+    Invpcid => "invpcid",
+    Rtm => "rtm",
+    Avx512f => "avx512f",
+    Avx512dq => "avx512dq",
+    Rdseed => "rdseed",
+    Adx => "adx",
+    Smap => "smap",
+    Avx512ifma => "avx512ifma",
+    Clflushopt => "clflushopt",
+    Clwb => "clwb",
+    Avx512cd => "avx512cd",
+    ShaNi => "sha_ni",
+    Avx512bw => "avx512bw",
+    Avx512vl => "avx512vl",
+    Xsaveopt => "xsaveopt",
+    Xsavec => "xsavec",
+    Xsaves => "xsaves",
+    Avx512vbmi => "avx512vbmi",
+    Umip => "umip",
+    Pku => "pku",
+    Avx512Vbmi2 => "avx512_vbmi2",
+    Gfni => "gfni",
+    Vaes => "vaes",
+    Vpclmulqdq => "vpclmulqdq",
+    Avx512Vnni => "avx512_vnni",
+    Avx512Bitalg => "avx512_bitalg",
+    Avx512Vpopcntdq => "avx512_vpopcntdq",
+    La57 => "la57",
+    Rdpid => "rdpid",
+    Avx512Bf16 => "avx512_bf16",
+    Avx512Fp16 => "avx512_fp16",
+    AvxVnni => "avx_vnni",
+    AmxBf16 => "amx_bf16",
+    AmxTile => "amx_tile",
+    AmxInt8 => "amx_int8",
+    Sgx => "sgx",
+    MdClear => "md_clear",
+    HybridCpu => "hybrid_cpu",
+    Ibrs => "ibrs",
+    Ibpb => "ibpb",
+    Stibp => "stibp",
+    Ssbd => "ssbd",
+    // shared by x86 and ARM
+    Aes => "aes",
+    Cpuid => "cpuid",
+    // ARM
+    Fp => "fp",
+    Asimd => "asimd",
+    Evtstrm => "evtstrm",
+    Pmull => "pmull",
+    Sha1 => "sha1",
+    Sha2 => "sha2",
+    Crc32 => "crc32",
+    Atomics => "atomics",
+    Fphp => "fphp",
+    Asimdhp => "asimdhp",
+    Asimdrdm => "asimdrdm",
+    Jscvt => "jscvt",
+    Fcma => "fcma",
+    Lrcpc => "lrcpc",
+    Dcpop => "dcpop",
+    Sha3 => "sha3",
+    Sm3 => "sm3",
+    Sm4 => "sm4",
+    Asimddp => "asimddp",
+    Sha512 => "sha512",
+    Sve => "sve",
+    Asimdfhm => "asimdfhm",
+    Dit => "dit",
+    Uscat => "uscat",
+    Ilrcpc => "ilrcpc",
+    Flagm => "flagm",
+    Ssbs => "ssbs",
+    Sb => "sb",
+    Paca => "paca",
+    Pacg => "pacg",
+    Dcpodp => "dcpodp",
+    Sve2 => "sve2",
+    Sveaes => "sveaes",
+    Svebitperm => "svebitperm",
+    I8mm => "i8mm",
+    Bf16 => "bf16",
+    Bti => "bti",
+    Mte => "mte",
+    // RISC-V, single letter extensions of the ISA string
+    I => "i",
+    M => "m",
+    A => "a",
+    F => "f",
+    D => "d",
+    Q => "q",
+    C => "c",
+    V => "v",
+    H => "h",
+    // RISC-V, multi-letter extensions
+    Zicsr => "zicsr",
+    Zifencei => "zifencei",
+    Zicntr => "zicntr",
+    Zihpm => "zihpm",
+    Zicbom => "zicbom",
+    Zicboz => "zicboz",
+    Zihintpause => "zihintpause",
+    Zba => "zba",
+    Zbb => "zbb",
+    Zbc => "zbc",
+    Zbs => "zbs",
+    Zfh => "zfh",
+    Zkt => "zkt",
+    Svpbmt => "svpbmt",
+    // LoongArch
+    Lam => "lam",
+    Ual => "ual",
+    Lsx => "lsx",
+    Lasx => "lasx",
+    Crypto => "crypto",
+    Lvz => "lvz",
+}
+
+const WORDS: usize = 4;
+const _: () = assert!(CpuFlag::ALL.len() <= WORDS * 64);
+
+/// CpuFlags is the set of features a CPU advertises. Well-known flags are
+/// kept in a bitmap, the others by name.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CpuFlags {
+    known: [u64; WORDS],
+    unknown: BTreeSet<String>,
+}
+
+impl CpuFlags {
+    pub fn new() -> CpuFlags {
+        CpuFlags::default()
+    }
+
+    pub fn contains(&self, flag: CpuFlag) -> bool {
+        let i = flag as usize;
+        self.known[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// contains_name is like contains, but also finds flags that have no
+    /// CpuFlag constant.
+    pub fn contains_name(&self, name: &str) -> bool {
+        match CpuFlag::from_name(name) {
+            Some(flag) => self.contains(flag),
+            None => self.unknown.contains(name),
+        }
+    }
+
+    pub fn insert(&mut self, flag: CpuFlag) {
+        let i = flag as usize;
+        self.known[i / 64] |= 1 << (i % 64);
+    }
+
+    /// insert_name adds a flag by name. Empty names are ignored.
+    pub fn insert_name(&mut self, name: &str) {
+        match CpuFlag::from_name(name) {
+            Some(flag) => self.insert(flag),
+            None if !name.is_empty() => {
+                self.unknown.insert(name.to_string());
+            }
+            None => {}
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.known.iter().map(|x| x.count_ones() as usize).sum::<usize>() + self.unknown.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// known returns the well-known flags of the set.
+    pub fn known(&self) -> impl Iterator<Item = CpuFlag> + '_ {
+        CpuFlag::ALL.iter().copied().filter(|x| self.contains(*x))
+    }
+
+    /// unknown returns, sorted, the flags that have no CpuFlag constant.
+    pub fn unknown(&self) -> impl Iterator<Item = &str> + '_ {
+        self.unknown.iter().map(String::as_str)
+    }
+
+    /// iter returns the names of all flags, the well-known ones first.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.known().map(|x| -> &'a str { x.name() }).chain(self.unknown())
+    }
+}
+
+/// CpuFlags parses from a /proc/cpuinfo flag list, separated by spaces or commas.
+impl FromStr for CpuFlags {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<CpuFlags, Infallible> {
+        Ok(s.split([' ', ',', '\t']).collect())
+    }
+}
+
+impl fmt::Display for CpuFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, name) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> FromIterator<&'a str> for CpuFlags {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> CpuFlags {
+        let mut ret = CpuFlags::new();
+        ret.extend(iter);
+        ret
+    }
+}
+
+impl FromIterator<CpuFlag> for CpuFlags {
+    fn from_iter<I: IntoIterator<Item = CpuFlag>>(iter: I) -> CpuFlags {
+        let mut ret = CpuFlags::new();
+        ret.extend(iter);
+        ret
+    }
+}
+
+impl<'a> Extend<&'a str> for CpuFlags {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for name in iter {
+            self.insert_name(name.trim());
+        }
+    }
+}
+
+impl Extend<CpuFlag> for CpuFlags {
+    fn extend<I: IntoIterator<Item = CpuFlag>>(&mut self, iter: I) {
+        for flag in iter {
+            self.insert(flag);
+        }
+    }
+}
@@ -1,56 +1,79 @@
-use std::collections::HashMap;
-use std::error::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
 
+use crate::common::cgroup::{cgroup_of, Cgroup};
 use crate::common::fs as cfs;
-use crate::cpu::{InfoStat, TimesStat};
-
-const PROC_STAT: &str = "/proc/stat";
-const PROC_CPUINFO: &str = "/proc/cpuinfo";
-const SYS_CPU: &str = "/sys/devices/system/cpu";
-const SYS: &str = "/sys";
-const CLOCKS_PER_SEC: f64 = 100.0;
-
-pub fn total_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
-    let lines = cfs::read_lines_offset_n(PROC_STAT, 0, 1)?;
+use crate::common::parse::parse_field;
+use crate::common::sys::clock_ticks;
+use crate::context::Context;
+use crate::cpu::arm;
+#[cfg(target_arch = "x86_64")]
+use crate::cpu::cpuid::cpuid_info;
+use crate::cpu::{
+    CacheStat, CoreStat, CpuSet, CpuSetKind, DieStat, EffectiveCountsStat, FreqStat, InfoStat, InterruptStat, NumaNodeStat,
+    PackageStat, SchedDomainStat, SchedStat, SoftInterruptStat, StatsStat, TimesStat, TopologyStat, VulnerabilityStat, VulnerabilityStatus,
+};
+use crate::error::{Error, Result};
+
+const PROC_STAT: &str = "stat";
+const PROC_CPUINFO: &str = "cpuinfo";
+const PROC_INTERRUPTS: &str = "interrupts";
+const PROC_SOFTIRQS: &str = "softirqs";
+const PROC_SCHEDSTAT: &str = "schedstat";
+const SYS_CPU: &str = "devices/system/cpu";
+const SYS_NODE: &str = "devices/system/node";
+const SYS_VULNERABILITIES: &str = "devices/system/cpu/vulnerabilities";
+
+// Upper bound of the affinity mask, in words: far beyond any NR_CPUS
+const MAX_CPU_MASK_LEN: usize = 1 << 16;
+
+pub fn total_cpu_times(ctx: &Context) -> Result<Vec<TimesStat>> {
+    let path = ctx.proc_path(PROC_STAT);
+    let lines = cfs::read_lines_offset_n(&path, 0, 1)?;
 
     let mut ret = Vec::with_capacity(lines.len());
-    for line in lines {
-        let state = parse_stat_line(&line)?;
+    for (i, line) in lines.iter().enumerate() {
+        let state = parse_stat_line(line, &path, i + 1)?;
         ret.push(state)
     }
 
     Ok(ret)
 }
 
-pub fn per_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
-    let lines = cfs::read_lines(PROC_STAT)?;
+pub fn per_cpu_times(ctx: &Context) -> Result<Vec<TimesStat>> {
+    let path = ctx.proc_path(PROC_STAT);
+    let lines = cfs::read_lines(&path)?;
 
     let mut ret = Vec::new();
     if lines.len() < 2 {
         return Ok(ret);
     }
 
-    for line in lines[1..].iter() {
+    for (i, line) in lines.iter().enumerate().skip(1) {
         if !line.starts_with("cpu") {
             break;
         }
 
-        let stat = parse_stat_line(line)?;
+        let stat = parse_stat_line(line, &path, i + 1)?;
         ret.push(stat)
     }
 
     Ok(ret)
 }
 
-pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
-    let lines = cfs::read_lines(PROC_CPUINFO)?;
+pub fn all_infos(ctx: &Context) -> Result<Vec<InfoStat>> {
+    let path = ctx.proc_path(PROC_CPUINFO);
+    let lines = cfs::read_lines(&path)?;
 
     let mut stat = InfoStat { cpu: -1, cores: 1, ..Default::default() };
     let mut process_name = String::new();
+    let mut vendor_id = String::new();
+    let mut implementer = None;
     let mut ret: Vec<InfoStat> = Vec::new();
 
-    for i in lines {
-        let fields: Vec<String> = i.split(":").map(String::from).collect();
+    for (i, line) in lines.iter().enumerate() {
+        let fields: Vec<String> = line.split(":").map(String::from).collect();
         if fields.len() < 2 {
             continue;
         }
@@ -63,117 +86,47 @@ pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
             "Processor" => process_name = value,
             "processor" | "cpu number" => {
                 if stat.cpu >= 0 {
-                    finish_cpu_info(&mut stat);
+                    finish_cpu_info(ctx, &mut stat);
                     ret.push(stat);
                 }
 
-                stat = InfoStat { cores: 1, model_name: process_name.clone(), ..Default::default() };
-                let t = value.parse::<i32>()?;
+                stat = InfoStat {
+                    cores: 1,
+                    vendor_id: vendor_id.clone(),
+                    model_name: process_name.clone(),
+                    ..Default::default()
+                };
+                implementer = None;
+                let t = parse_field::<i32, _>(&value, &path, i + 1, key)?;
                 stat.cpu = t;
             }
             "vendorId" | "vendor_id" => {
+                // s390x reports vendor_id once, before the first "cpu number"
                 if value.contains("S390") {
                     process_name = "S390".to_string();
+                    vendor_id = value.clone();
                 }
                 stat.vendor_id = value
             }
             "CPU implementer" => {
-                if let Ok(v) = usize::from_str_radix(value.as_str(), 8) {
-                    stat.vendor_id = String::from(match v {
-                        0x41 => "ARM",
-                        0x42 => "Broadcom",
-                        0x43 => "Cavium",
-                        0x44 => "DEC",
-                        0x46 => "Fujitsu",
-                        0x48 => "HiSilicon",
-                        0x49 => "Infineon",
-                        0x4d => "Motorola/Freescale",
-                        0x4e => "NVIDIA",
-                        0x50 => "APM",
-                        0x51 => "Qualcomm",
-                        0x56 => "Marvell",
-                        0x61 => "Apple",
-                        0x69 => "Intel",
-                        0xc0 => "Ampere",
-                        _ => ""
-                    });
+                implementer = parse_hex(&value);
+                if let Some(v) = implementer {
+                    stat.vendor_id = arm::implementer_name(v).unwrap_or_default().to_string();
                 }
             }
             "cpu family" => {
                 stat.family = value
             }
-            "model" | "CPU part" => {
+            "model" => {
                 stat.model = value;
-                // if CPU is arm based, model name is found via model number. refer to: arch/arm64/kernel/cpuinfo.c
-                if stat.vendor_id == "ARM" {
-                    if let Ok(v) = usize::from_str_radix(&stat.model, 0) {
-                        stat.model_name = String::from(match v {
-                            0x810 => "ARM810",
-                            0x920 => "ARM920",
-                            0x922 => "ARM922",
-                            0x926 => "ARM926",
-                            0x940 => "ARM940",
-                            0x946 => "ARM946",
-                            0x966 => "ARM966",
-                            0xa20 => "ARM1020",
-                            0xa22 => "ARM1022",
-                            0xa26 => "ARM1026",
-                            0xb02 => "ARM11 MPCore",
-                            0xb36 => "ARM1136",
-                            0xb56 => "ARM1156",
-                            0xb76 => "ARM1176",
-                            0xc05 => "Cortex-A5",
-                            0xc07 => "Cortex-A7",
-                            0xc08 => "Cortex-A8",
-                            0xc09 => "Cortex-A9",
-                            0xc0d => "Cortex-A17",
-                            0xc0f => "Cortex-A15",
-                            0xc0e => "Cortex-A17",
-                            0xc14 => "Cortex-R4",
-                            0xc15 => "Cortex-R5",
-                            0xc17 => "Cortex-R7",
-                            0xc18 => "Cortex-R8",
-                            0xc20 => "Cortex-M0",
-                            0xc21 => "Cortex-M1",
-                            0xc23 => "Cortex-M3",
-                            0xc24 => "Cortex-M4",
-                            0xc27 => "Cortex-M7",
-                            0xc60 => "Cortex-M0+",
-                            0xd01 => "Cortex-A32",
-                            0xd02 => "Cortex-A34",
-                            0xd03 => "Cortex-A53",
-                            0xd04 => "Cortex-A35",
-                            0xd05 => "Cortex-A55",
-                            0xd06 => "Cortex-A65",
-                            0xd07 => "Cortex-A57",
-                            0xd08 => "Cortex-A72",
-                            0xd09 => "Cortex-A73",
-                            0xd0a => "Cortex-A75",
-                            0xd0b => "Cortex-A76",
-                            0xd0c => "Neoverse-N1",
-                            0xd0d => "Cortex-A77",
-                            0xd0e => "Cortex-A76AE",
-                            0xd13 => "Cortex-R52",
-                            0xd20 => "Cortex-M23",
-                            0xd21 => "Cortex-M33",
-                            0xd40 => "Neoverse-V1",
-                            0xd41 => "Cortex-A78",
-                            0xd42 => "Cortex-A78AE",
-                            0xd43 => "Cortex-A65AE",
-                            0xd44 => "Cortex-X1",
-                            0xd46 => "Cortex-A510",
-                            0xd47 => "Cortex-A710",
-                            0xd48 => "Cortex-X2",
-                            0xd49 => "Neoverse-N2",
-                            0xd4a => "Neoverse-E1",
-                            0xd4b => "Cortex-A78C",
-                            0xd4c => "Cortex-X1C",
-                            0xd4d => "Cortex-A715",
-                            0xd4e => "Cortex-X3",
-                            _ => "Undefined"
-                        })
-                    }
+            }
+            "CPU part" => {
+                // the model name of ARM CPUs is found via implementer and part number.
+                // refer to: arch/arm64/include/asm/cputype.h
+                if let (Some(implementer), Some(part)) = (implementer, parse_hex(&value)) {
+                    stat.model_name = arm::part_name(implementer, part).unwrap_or("Undefined").to_string();
                 }
+                stat.model = value;
             }
             "Model Name" | "model name" | "cpu" => {
                 if value.contains("POWER") {
@@ -185,9 +138,52 @@ pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
             }
             "stepping" | "revision" | "CPU revision" => {
                 let val: String = if key == "revision" { value.split(".").next().unwrap_or("").to_string() } else { value };
-                stat.stepping = val.parse::<i32>()?;
+                stat.stepping = parse_field(&val, &path, i + 1, key)?;
+            }
+            // LoongArch
+            "CPU Family" => {
+                if value.starts_with("Loongson") {
+                    stat.vendor_id = "Loongson".to_string();
+                }
+                stat.family = value;
+            }
+            "CPU Revision" => {
+                if let Some(v) = parse_hex(&value) {
+                    stat.stepping = v as i32;
+                }
+            }
+            "package" => {
+                stat.physical_id = value;
+            }
+            "core" => {
+                stat.core_id = value;
+            }
+            // RISC-V
+            "hart" => {
+                stat.core_id = value;
+            }
+            "isa" | "hart isa" => {
+                // "hart isa" is this hart's, "isa" the set common to all harts
+                stat.flags.extend(riscv_extensions(&value).iter().map(String::as_str));
+            }
+            "uarch" => {
+                stat.model_name = value;
+            }
+            "mvendorid" => {
+                stat.vendor_id = match parse_hex(&value) {
+                    Some(0x31e) => "Andes".to_string(),
+                    Some(0x489) => "SiFive".to_string(),
+                    Some(0x5b7) => "T-Head".to_string(),
+                    _ => value,
+                };
+            }
+            "marchid" => {
+                stat.model = value;
             }
-            "cpu MHz" | "clock" | "cpu MHz dynamic" => {
+            "mimpid" => {
+                stat.microcode = value;
+            }
+            "cpu MHz" | "CPU MHz" | "clock" | "cpu MHz dynamic" => {
                 // treat this as the fallback value, thus we ignore error
                 if let Ok(v) = value.replace("MHz", "").parse::<_>() {
                     stat.mhz = v;
@@ -205,7 +201,7 @@ pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
                 stat.core_id = value;
             }
             "flags" | "Features" => {
-                stat.flags = value.split(|x| x == ',' || x == ' ').map(String::from).collect();
+                stat.flags = value.split([',', ' ']).collect();
             }
             "microcode" => {
                 stat.microcode = value;
@@ -215,17 +211,17 @@ pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
     }
 
     if stat.cpu >= 0 {
-        finish_cpu_info(&mut stat);
+        finish_cpu_info(ctx, &mut stat);
         ret.push(stat);
     }
 
     Ok(ret)
 }
 
-pub fn logical_counts() -> Result<u32, Box<dyn Error>> {
+pub fn logical_counts(ctx: &Context) -> Result<u32> {
     let mut ret = 0;
 
-    if let Ok(lines) = cfs::read_lines(PROC_CPUINFO) {
+    if let Ok(lines) = cfs::read_lines(ctx.proc_path(PROC_CPUINFO)) {
         for line in lines {
             let line = line.to_lowercase();
             if line.starts_with("processor") {
@@ -240,11 +236,11 @@ pub fn logical_counts() -> Result<u32, Box<dyn Error>> {
     }
 
     if ret == 0 {
-        let lines = cfs::read_lines(PROC_STAT)?;
+        let lines = cfs::read_lines(ctx.proc_path(PROC_STAT))?;
         for line in lines {
             if line.len() >= 4 &&
                 line.starts_with("cpu") &&
-                line.chars().nth(3).map_or(false, |c| { c.is_ascii_digit() }) {
+                line.chars().nth(3).is_some_and(|c| c.is_ascii_digit()) {
                 ret += 1;
             }
         }
@@ -253,51 +249,52 @@ pub fn logical_counts() -> Result<u32, Box<dyn Error>> {
     Ok(ret)
 }
 
-pub fn physical_counts() -> Result<u32, Box<dyn Error>> {
+pub fn physical_counts(ctx: &Context) -> Result<u32> {
     // physical cores
     // https://github.com/giampaolo/psutil/blob/8415355c8badc9c94418b19bdf26e622f06f0cce/psutil/_pslinux.py#L615-L628
     let mut thread_siblings_lists = HashMap::new();
+    // the trailing separator of sys_path("") joins the relative patterns below
+    let sys_root = glob::Pattern::escape(&ctx.sys_path("").to_string_lossy());
 
     // These 2 files are the same but */core_cpus_list is newer while */thread_siblings_list is deprecated and may disappear in the future.
     // https://www.kernel.org/doc/Documentation/admin-guide/cputopology.rst
     // https://github.com/giampaolo/psutil/pull/1727#issuecomment-707624964
     // https://lkml.org/lkml/2019/2/26/41
-    for glob_pattern in vec![
+    for glob_pattern in [
         "devices/system/cpu/cpu[0-9]*/topology/core_cpus_list",
         "devices/system/cpu/cpu[0-9]*/topology/thread_siblings_list",
     ] {
-        let full_pattern = format!("{}/{}", SYS, glob_pattern);
+        let full_pattern = format!("{}{}", sys_root, glob_pattern);
         if let Ok(files) = glob::glob(&full_pattern) {
-            for file_result in files {
-                if let Ok(file) = file_result {
-                    if let Ok(lines) = cfs::read_lines(file.as_path()) {
-                        if lines.len() != 1 {
-                            continue;
-                        }
-                        thread_siblings_lists.insert(lines[0].clone(), true);
+            for file in files.flatten() {
+                if let Ok(lines) = cfs::read_lines(file.as_path()) {
+                    if lines.len() != 1 {
+                        continue;
                     }
+                    thread_siblings_lists.insert(lines[0].clone(), true);
                 }
             }
 
-            if thread_siblings_lists.len() != 0 {
+            if !thread_siblings_lists.is_empty() {
                 return Ok(thread_siblings_lists.len() as u32);
             }
         }
     }
 
     // https://github.com/giampaolo/psutil/blob/122174a10b75c9beebe15f6c07dcf3afbe3b120d/psutil/_pslinux.py#L631-L652
-    let lines = cfs::read_lines(PROC_CPUINFO)?;
+    let lines = cfs::read_lines(ctx.proc_path(PROC_CPUINFO))?;
     let mut mapping = HashMap::new();
     let mut current_info = HashMap::new();
 
     for line in lines {
         let line = line.trim().to_lowercase();
-        if line.len() == 0 {
+        if line.is_empty() {
             // new section
             if let (Some(&id), Some(&cores)) =
-                (current_info.get("phusical id"), current_info.get("cpu cores")) {
+                (current_info.get("physical id"), current_info.get("cpu cores")) {
                 mapping.insert(id, cores);
             }
+            current_info.clear();
             continue;
         }
 
@@ -317,33 +314,585 @@ pub fn physical_counts() -> Result<u32, Box<dyn Error>> {
     Ok(mapping.values().map(|&x| { x as u32 }).sum())
 }
 
-fn parse_stat_line(line: &str) -> Result<TimesStat, Box<dyn Error>> {
+pub fn cpu_freqs(ctx: &Context) -> Result<Vec<FreqStat>> {
+    let cpuinfo_mhz = cpuinfo_mhz(ctx);
+    let cpus = cpu_dirs(ctx)?;
+    let mut ret = Vec::with_capacity(cpus.len());
+
+    for cpu in cpus.iter() {
+        let freq_dir = ctx.sys_path(format!("{}/cpu{}/cpufreq", SYS_CPU, cpu));
+        let khz = |names: &[&str]| {
+            names.iter().find_map(|name| {
+                cfs::read_line(freq_dir.join(name)).ok()?.parse::<f64>().ok()
+            }).map(|v| v / 1000.0)
+        };
+
+        let current = khz(&["scaling_cur_freq", "cpuinfo_cur_freq"])
+            .or_else(|| cpuinfo_mhz.get(cpu).copied())
+            .unwrap_or(0.0);
+
+        ret.push(FreqStat {
+            cpu: format!("cpu{}", cpu),
+            current,
+            min: khz(&["scaling_min_freq", "cpuinfo_min_freq"]).unwrap_or(0.0),
+            max: khz(&["scaling_max_freq", "cpuinfo_max_freq"]).unwrap_or(0.0),
+            governor: cfs::read_line(freq_dir.join("scaling_governor")).unwrap_or_default(),
+            available_governors: cfs::read_line(freq_dir.join("scaling_available_governors"))
+                .map(|v| v.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+        });
+    }
+
+    // no /sys, e.g. in a container: /proc/cpuinfo is all we have
+    if ret.is_empty() {
+        ret = cpuinfo_mhz.iter().map(|(cpu, &mhz)| FreqStat {
+            cpu: format!("cpu{}", cpu),
+            current: mhz,
+            ..Default::default()
+        }).collect();
+    }
+
+    Ok(ret)
+}
+
+pub fn cpu_stats(ctx: &Context) -> Result<StatsStat> {
+    let path = ctx.proc_path(PROC_STAT);
+    let lines = cfs::read_lines(&path)?;
+    let mut ret = StatsStat::default();
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut fields = line.split_whitespace();
+        let (Some(key), Some(value)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        // the first value of intr and softirq is the total over all sources
+        let field = match key {
+            "ctxt" => &mut ret.ctx_switches,
+            "intr" => &mut ret.interrupts,
+            "softirq" => &mut ret.soft_interrupts,
+            _ => continue,
+        };
+        *field = parse_field(value, &path, i + 1, key)?;
+    }
+
+    Ok(ret)
+}
+
+pub fn all_interrupts(ctx: &Context) -> Result<Vec<InterruptStat>> {
+    let path = ctx.proc_path(PROC_INTERRUPTS);
+    let rows = parse_per_cpu_table(&cfs::read_lines(&path)?, &path)?;
+
+    Ok(rows.into_iter().map(|(irq, per_cpu, description)| {
+        InterruptStat { irq, per_cpu, description }
+    }).collect())
+}
+
+pub fn all_soft_interrupts(ctx: &Context) -> Result<Vec<SoftInterruptStat>> {
+    let path = ctx.proc_path(PROC_SOFTIRQS);
+    let rows = parse_per_cpu_table(&cfs::read_lines(&path)?, &path)?;
+
+    Ok(rows.into_iter().map(|(name, per_cpu, _)| {
+        SoftInterruptStat { name, per_cpu }
+    }).collect())
+}
+
+pub fn cpu_topology(ctx: &Context) -> Result<TopologyStat> {
+    let mut ret = TopologyStat::default();
+
+    for cpu in cpu_dirs(ctx)? {
+        let dir = ctx.sys_path(format!("{}/cpu{}", SYS_CPU, cpu));
+        let id = |name: &str| -> Option<i32> {
+            cfs::read_line(dir.join("topology").join(name)).ok()?.parse().ok()
+        };
+
+        // offline CPUs have no topology
+        let Some(core_id) = id("core_id") else {
+            continue;
+        };
+        let package_id = id("physical_package_id").unwrap_or(0);
+        let die_id = id("die_id").unwrap_or(0);
+
+        let package = match ret.packages.iter().position(|x| x.id == package_id) {
+            Some(i) => &mut ret.packages[i],
+            None => {
+                ret.packages.push(PackageStat { id: package_id, ..Default::default() });
+                ret.packages.last_mut().unwrap()
+            }
+        };
+        let die = match package.dies.iter().position(|x| x.id == die_id) {
+            Some(i) => &mut package.dies[i],
+            None => {
+                package.dies.push(DieStat { id: die_id, ..Default::default() });
+                package.dies.last_mut().unwrap()
+            }
+        };
+        match die.cores.iter_mut().find(|x| x.id == core_id) {
+            Some(core) => {
+                core.cpus.insert(cpu);
+            }
+            None => die.cores.push(CoreStat { id: core_id, cpus: [cpu].into_iter().collect() }),
+        }
+
+        for cache in cpu_caches(&dir.join("cache"))? {
+            if !ret.caches.contains(&cache) {
+                ret.caches.push(cache);
+            }
+        }
+    }
+
+    ret.packages.sort_by_key(|x| x.id);
+    for package in ret.packages.iter_mut() {
+        package.dies.sort_by_key(|x| x.id);
+        for die in package.dies.iter_mut() {
+            die.cores.sort_by_key(|x| x.id);
+        }
+    }
+    ret.caches.sort_by(|a, b| (a.level, &a.cache_type, &a.shared_cpus).cmp(&(b.level, &b.cache_type, &b.shared_cpus)));
+    ret.numa_nodes = numa_nodes(ctx)?;
+
+    Ok(ret)
+}
+
+pub fn cpu_list(ctx: &Context, kind: CpuSetKind) -> Result<CpuSet> {
+    let name = match kind {
+        CpuSetKind::Online => "online",
+        CpuSetKind::Offline => "offline",
+        CpuSetKind::Possible => "possible",
+        CpuSetKind::Present => "present",
+        CpuSetKind::Isolated => "isolated",
+        CpuSetKind::NohzFull => "nohz_full",
+    };
+
+    let path = ctx.sys_path(format!("{}/{}", SYS_CPU, name));
+    let value = match cfs::read_line(&path) {
+        Ok(v) => v,
+        // isolated and nohz_full only exist if the kernel supports them,
+        // in which case nothing can be isolated
+        Err(Error::Io { source, .. })
+            if source.kind() == io::ErrorKind::NotFound
+                && matches!(kind, CpuSetKind::Isolated | CpuSetKind::NohzFull) => return Ok(CpuSet::new()),
+        Err(e) => return Err(e),
+    };
+
+    // nohz_full prints "(null)" when booted without the parameter
+    if value == "(null)" {
+        return Ok(CpuSet::new());
+    }
+    parse_cpu_list(&value, &path)
+}
+
+pub fn cpu_vulnerabilities(ctx: &Context) -> Result<Vec<VulnerabilityStat>> {
+    let path = ctx.sys_path(SYS_VULNERABILITIES);
+    let entries = match std::fs::read_dir(&path) {
+        Ok(v) => v,
+        // kernels before 4.15 do not report anything
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::io(&path, e)),
+    };
+
+    let mut ret = vec![];
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let value = cfs::read_line(entry.path())?;
+
+        // e.g. "Not affected", "Mitigation: PTI", "Vulnerable; SMT vulnerable",
+        // "Unknown: <details>", or for itlb_multihit "KVM: Mitigation: VMX disabled"
+        let report = value.strip_prefix("KVM: ").unwrap_or(&value);
+        let (status, mitigation) = if report.starts_with("Not affected") {
+            (VulnerabilityStatus::NotAffected, "")
+        } else if let Some(details) = report.strip_prefix("Mitigation") {
+            (VulnerabilityStatus::Mitigated, details.trim_start_matches(':').trim())
+        } else if report.starts_with("Vulnerable") || report.starts_with("Processor vulnerable") {
+            (VulnerabilityStatus::Vulnerable, "")
+        } else {
+            (VulnerabilityStatus::Unknown, "")
+        };
+
+        ret.push(VulnerabilityStat { name, status, mitigation: mitigation.to_string(), description: value });
+    }
+
+    ret.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ret)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid_cpu_infos() -> Result<Vec<InfoStat>> {
+    // CPUID answers for the CPU it runs on, so visit every allowed CPU from a
+    // scratch thread whose affinity can be changed freely
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            sched_affinity()?.iter().map(|cpu| {
+                pin_to_cpu(cpu)?;
+                Ok(cpuid_info(cpu as i32))
+            }).collect()
+        }).join().unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}
+
+pub fn cpu_sched_stats(ctx: &Context) -> Result<Vec<SchedStat>> {
+    let path = ctx.proc_path(PROC_SCHEDSTAT);
+    let lines = cfs::read_lines(&path)?;
+
+    let mut version = 0;
+    let mut ret: Vec<SchedStat> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let fields = line.split_ascii_whitespace().collect::<Vec<&str>>();
+        if fields.is_empty() {
+            continue;
+        }
+
+        if fields[0] == "version" && fields.len() > 1 {
+            version = parse_field::<u32, _>(fields[1], &path, i + 1, "version")?;
+        } else if let Some(cpu) = fields[0].strip_prefix("cpu") {
+            // cpu<N> yld_count 0 sched_count sched_goidle ttwu_count ttwu_local
+            // rq_cpu_time run_delay pcount
+            if fields.len() < 10 {
+                return Err(Error::parse(&path, i + 1, fields[0], "too few fields"));
+            }
+            let field = |n: usize| parse_field::<u64, _>(fields[n], &path, i + 1, fields[0]);
+            ret.push(SchedStat {
+                cpu: parse_field(cpu, &path, i + 1, "cpu")?,
+                sched_count: field(3)?,
+                sched_goidle: field(4)?,
+                ttwu_count: field(5)?,
+                ttwu_local: field(6)?,
+                run_time: field(7)?,
+                wait_time: field(8)?,
+                timeslices: field(9)?,
+                domains: vec![],
+            });
+        } else if let Some(level) = fields[0].strip_prefix("domain") {
+            let Some(stat) = ret.last_mut() else {
+                return Err(Error::parse(&path, i + 1, fields[0], "domain before any cpu"));
+            };
+
+            // domain<N> [<name>] <cpumask> <lb stats for idle, busy and newly
+            // idle> <alb stats> <sbe stats> <sbf stats> <ttwu stats>. Version
+            // 17 added the name and splits lb_imbalance into four fields.
+            let (name, fields) = if version >= 17 { (fields[1], &fields[2..]) } else { ("", &fields[1..]) };
+            let lb_len = if version >= 17 { 11 } else { 8 };
+            if fields.len() < 1 + 3 * lb_len + 12 {
+                return Err(Error::parse(&path, i + 1, "domain", "too few fields"));
+            }
+
+            let field = |n: usize| parse_field::<u64, _>(fields[n], &path, i + 1, "domain");
+            let lb_sum = |n: usize| -> Result<u64> { Ok(field(1 + n)? + field(1 + lb_len + n)? + field(1 + 2 * lb_len + n)?) };
+            let rest = 1 + 3 * lb_len;
+            stat.domains.push(SchedDomainStat {
+                level: parse_field(level, &path, i + 1, "domain")?,
+                name: name.to_string(),
+                cpus: parse_cpu_mask(fields[0]).ok_or_else(|| Error::parse(&path, i + 1, "cpumask", "invalid mask"))?,
+                lb_count: lb_sum(0)?,
+                lb_balanced: lb_sum(1)?,
+                lb_failed: lb_sum(2)?,
+                alb_count: field(rest)?,
+                alb_failed: field(rest + 1)?,
+                alb_pushed: field(rest + 2)?,
+                ttwu_wake_remote: field(rest + 9)?,
+                ttwu_move_affine: field(rest + 10)?,
+                ttwu_move_balance: field(rest + 11)?,
+            });
+        }
+    }
+
+    Ok(ret)
+}
+
+pub fn effective_cpu_counts(ctx: &Context) -> Result<EffectiveCountsStat> {
+    let affinity = sched_affinity()?.len() as u32;
+
+    let cpuset = cgroup_of(ctx, "cpuset").and_then(|cgroup| {
+        let names: &[&str] = match cgroup {
+            Cgroup::V1 { .. } => &["cpuset.effective_cpus", "cpuset.cpus"],
+            Cgroup::V2 { .. } => &["cpuset.cpus.effective"],
+        };
+        names.iter().find_map(|name| {
+            let path = cgroup.dir().join(name);
+            parse_cpu_list(&cfs::read_line(&path).ok()?, &path).ok()
+        })
+    }).filter(|x| !x.is_empty()).map(|x| x.len() as u32);
+
+    // a limit set on any ancestor applies as well, the smallest one wins
+    let quota = cgroup_of(ctx, "cpu").and_then(|cgroup| {
+        cgroup.ancestors().filter_map(|dir| cpu_quota(&cgroup, dir)).min_by(|a, b| a.total_cmp(b))
+    });
+
+    let mut effective = affinity;
+    if let Some(cpuset) = cpuset {
+        effective = effective.min(cpuset);
+    }
+    if let Some(quota) = quota {
+        effective = effective.min(quota.ceil() as u32);
+    }
+
+    Ok(EffectiveCountsStat { affinity, cpuset, quota, effective: effective.max(1) })
+}
+
+/// cpu_quota returns the CPU bandwidth limit of a cgroup directory in CPUs,
+/// or None if it is unlimited.
+fn cpu_quota(cgroup: &Cgroup, dir: &Path) -> Option<f64> {
+    let (quota, period) = match cgroup {
+        // "$MAX $PERIOD", $MAX being "max" when unlimited
+        Cgroup::V2 { .. } => {
+            let line = cfs::read_line(dir.join("cpu.max")).ok()?;
+            let (quota, period) = line.split_once(' ')?;
+            (quota.parse::<f64>().ok()?, period.parse::<f64>().ok()?)
+        }
+        // cfs_quota_us is -1 when unlimited
+        Cgroup::V1 { .. } => (
+            cfs::read_line(dir.join("cpu.cfs_quota_us")).ok()?.parse::<f64>().ok()?,
+            cfs::read_line(dir.join("cpu.cfs_period_us")).ok()?.parse::<f64>().ok()?,
+        ),
+    };
+
+    if quota <= 0.0 || period <= 0.0 {
+        return None;
+    }
+    Some(quota / period)
+}
+
+/// sched_affinity returns the CPUs the calling thread may run on.
+fn sched_affinity() -> Result<CpuSet> {
+    // cpu_set_t holds 1024 CPUs, kernels built for more reject it with EINVAL:
+    // grow the mask until it is large enough, as glibc's CPU_ALLOC does
+    let mut mask = vec![0 as libc::c_ulong; cpu_mask_len(libc::CPU_SETSIZE as usize - 1)];
+    loop {
+        let size = mask.len() * std::mem::size_of::<libc::c_ulong>();
+        if unsafe { libc::sched_getaffinity(0, size, mask.as_mut_ptr() as *mut libc::cpu_set_t) } == 0 {
+            break;
+        }
+
+        let source = io::Error::last_os_error();
+        if source.raw_os_error() != Some(libc::EINVAL) || mask.len() >= MAX_CPU_MASK_LEN {
+            return Err(Error::Os { call: "sched_getaffinity", source });
+        }
+        mask.resize(mask.len() * 2, 0);
+    }
+
+    let bits = libc::c_ulong::BITS as usize;
+    Ok((0..mask.len() * bits).filter(|x| mask[x / bits] & (1 << (x % bits)) != 0).map(|x| x as u32).collect())
+}
+
+/// pin_to_cpu restricts the calling thread to a single CPU.
+#[cfg(target_arch = "x86_64")]
+fn pin_to_cpu(cpu: u32) -> Result<()> {
+    let bits = libc::c_ulong::BITS as usize;
+    let cpu = cpu as usize;
+    let mut mask = vec![0 as libc::c_ulong; cpu_mask_len(cpu.max(libc::CPU_SETSIZE as usize - 1))];
+    mask[cpu / bits] |= 1 << (cpu % bits);
+
+    let size = mask.len() * std::mem::size_of::<libc::c_ulong>();
+    if unsafe { libc::sched_setaffinity(0, size, mask.as_ptr() as *const libc::cpu_set_t) } != 0 {
+        return Err(Error::Os { call: "sched_setaffinity", source: io::Error::last_os_error() });
+    }
+    Ok(())
+}
+
+/// cpu_mask_len returns the number of words of a CPU mask holding CPUs 0 to `cpu`.
+fn cpu_mask_len(cpu: usize) -> usize {
+    cpu / libc::c_ulong::BITS as usize + 1
+}
+
+/// cpu_caches reads the cache descriptors of a CPU from its cache/index* directories.
+fn cpu_caches(dir: &Path) -> Result<Vec<CacheStat>> {
+    let mut ret = Vec::new();
+
+    for index in 0.. {
+        let index_dir = dir.join(format!("index{}", index));
+        if !index_dir.exists() {
+            break;
+        }
+
+        let read = |name: &str| cfs::read_line(index_dir.join(name)).unwrap_or_default();
+        let shared_path = index_dir.join("shared_cpu_list");
+        ret.push(CacheStat {
+            level: read("level").parse().unwrap_or(0),
+            cache_type: read("type"),
+            size: parse_size(&read("size")).unwrap_or(0),
+            line_size: read("coherency_line_size").parse().unwrap_or(0),
+            ways_of_associativity: read("ways_of_associativity").parse().unwrap_or(0),
+            shared_cpus: parse_cpu_list(&read("shared_cpu_list"), &shared_path)?,
+        });
+    }
+
+    Ok(ret)
+}
+
+/// numa_nodes reads the CPUs of every node in /sys/devices/system/node.
+fn numa_nodes(ctx: &Context) -> Result<Vec<NumaNodeStat>> {
+    let path = ctx.sys_path(SYS_NODE);
+    let entries = match std::fs::read_dir(&path) {
+        Ok(v) => v,
+        // kernels built without CONFIG_NUMA
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::io(&path, e)),
+    };
+
+    let mut ret = Vec::new();
+    for entry in entries.flatten() {
+        let Some(id) = entry.file_name().to_str()
+            .and_then(|x| x.strip_prefix("node"))
+            .and_then(|x| x.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        let cpulist = entry.path().join("cpulist");
+        ret.push(NumaNodeStat { id, cpus: parse_cpu_list(&cfs::read_line(&cpulist)?, &cpulist)? });
+    }
+    ret.sort_by_key(|x| x.id);
+
+    Ok(ret)
+}
+
+/// parse_cpu_list parses the kernel's cpu list format, e.g. `0-3,8-11`.
+fn parse_cpu_list(value: &str, path: &Path) -> Result<CpuSet> {
+    value.parse::<CpuSet>().map_err(|e| Error::parse(path, 1, "cpu list", e))
+}
+
+/// parse_cpu_mask parses a hexadecimal CPU bitmap such as "00000000,0000000f",
+/// the format of /proc/schedstat and the cpumap files of sysfs.
+fn parse_cpu_mask(value: &str) -> Option<CpuSet> {
+    let mut ret = CpuSet::new();
+    let digits = value.chars().filter(|x| *x != ',').collect::<Vec<char>>();
+    for (i, c) in digits.iter().rev().enumerate() {
+        let v = c.to_digit(16)?;
+        for bit in 0..4 {
+            if v & (1 << bit) != 0 {
+                ret.insert((i * 4 + bit) as u32);
+            }
+        }
+    }
+    Some(ret)
+}
+
+/// parse_size parses sysfs sizes such as `48K` into bytes.
+fn parse_size(value: &str) -> Option<u64> {
+    let (digits, shift) = match value.chars().last()? {
+        'K' => (&value[..value.len() - 1], 10),
+        'M' => (&value[..value.len() - 1], 20),
+        'G' => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+    digits.parse::<u64>().ok().map(|v| v << shift)
+}
+
+/// parse_per_cpu_table parses the layout shared by /proc/interrupts and
+/// /proc/softirqs: a header naming the online CPUs, then one row per source
+/// with a counter per CPU and an optional description. The counters are
+/// indexed by CPU number; CPUs missing from the header (offline) read 0.
+fn parse_per_cpu_table(lines: &[String], path: &Path) -> Result<Vec<(String, Vec<u64>, String)>> {
+    let Some(header) = lines.first() else {
+        return Ok(vec![]);
+    };
+
+    let mut cpus = Vec::new();
+    for name in header.split_whitespace() {
+        let cpu = name.strip_prefix("CPU").unwrap_or(name);
+        cpus.push(parse_field::<usize, _>(cpu, path, 1, "header")?);
+    }
+    let width = cpus.iter().max().map_or(0, |&x| x + 1);
+
+    let mut ret = Vec::new();
+    for line in lines.iter().skip(1) {
+        let Some((name, rest)) = line.split_once(':') else {
+            continue;
+        };
+
+        let mut per_cpu = vec![0; width];
+        let mut fields = rest.split_whitespace().peekable();
+        // rows such as ERR and MIS carry a single system-wide counter
+        for &cpu in &cpus {
+            match fields.peek().and_then(|x| x.parse::<u64>().ok()) {
+                Some(v) => per_cpu[cpu] = v,
+                None => break,
+            }
+            fields.next();
+        }
+
+        let description = fields.collect::<Vec<_>>().join(" ");
+        ret.push((name.trim().to_string(), per_cpu, description));
+    }
+
+    Ok(ret)
+}
+
+/// cpu_dirs returns the numbers of the cpu[0-9]* directories in sysfs, sorted.
+fn cpu_dirs(ctx: &Context) -> Result<Vec<u32>> {
+    let path = ctx.sys_path(SYS_CPU);
+    let entries = match std::fs::read_dir(&path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::io(&path, e)),
+    };
+
+    let mut ret = entries.flatten().filter_map(|entry| {
+        entry.file_name().to_str()?.strip_prefix("cpu")?.parse::<u32>().ok()
+    }).collect::<Vec<_>>();
+    ret.sort_unstable();
+    Ok(ret)
+}
+
+/// cpuinfo_mhz returns the "cpu MHz" value of every processor in /proc/cpuinfo,
+/// by processor number. Offline CPUs are not listed there.
+fn cpuinfo_mhz(ctx: &Context) -> BTreeMap<u32, f64> {
+    let lines = cfs::read_lines(ctx.proc_path(PROC_CPUINFO)).unwrap_or_default();
+    let mut ret = BTreeMap::new();
+    let mut processor = None;
+
+    for line in &lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "processor" => processor = value.trim().parse::<u32>().ok(),
+            "cpu MHz" => {
+                if let (Some(cpu), Ok(mhz)) = (processor, value.trim().parse::<f64>()) {
+                    ret.insert(cpu, mhz);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ret
+}
+
+fn parse_stat_line(line: &str, path: &Path, line_no: usize) -> Result<TimesStat> {
     let fields: Vec<String> = line.split_whitespace().map(String::from).collect();
     if fields.len() < 8 {
-        return Err("stat does not contain cpu info".into());
+        return Err(Error::parse(path, line_no, "cpu", "stat does not contain cpu info"));
     }
 
     if !fields[0].starts_with("cpu") {
-        return Err("not contain cpu".into());
+        return Err(Error::parse(path, line_no, "cpu", "not contain cpu"));
     }
 
     let cpu = if fields[0].eq("cpu") { String::from("cpu-total") } else { fields[0].to_string() };
-    let user: f64 = fields[1].parse::<f64>()? / CLOCKS_PER_SEC;
-    let nice: f64 = fields[2].parse::<f64>()? / CLOCKS_PER_SEC;
-    let system: f64 = fields[3].parse::<f64>()? / CLOCKS_PER_SEC;
-    let idle: f64 = fields[4].parse::<f64>()? / CLOCKS_PER_SEC;
-    let io_wait: f64 = fields[5].parse::<f64>()? / CLOCKS_PER_SEC;
-    let irq: f64 = fields[6].parse::<f64>()? / CLOCKS_PER_SEC;
-    let soft_irq: f64 = fields[7].parse::<f64>()? / CLOCKS_PER_SEC;
+    let ticks = clock_ticks() as f64;
+    let field = |i: usize, name: &str| -> Result<f64> {
+        if i >= fields.len() {
+            return Ok(0.0);
+        }
+        Ok(parse_field::<f64, _>(&fields[i], path, line_no, name)? / ticks)
+    };
+
+    let user = field(1, "user")?;
+    let nice = field(2, "nice")?;
+    let system = field(3, "system")?;
+    let idle = field(4, "idle")?;
+    let io_wait = field(5, "iowait")?;
+    let irq = field(6, "irq")?;
+    let soft_irq = field(7, "softirq")?;
 
     // Linux >= 2.6.11
-    let steal = if fields.len() > 8 { fields[8].parse::<f64>()? / CLOCKS_PER_SEC } else { 0.0 };
+    let steal = field(8, "steal")?;
 
     // Linux >= 2.6.24
-    let guest = if fields.len() > 9 { fields[9].parse::<f64>()? / CLOCKS_PER_SEC } else { 0.0 };
+    let guest = field(9, "guest")?;
 
     // Linux >= 3.2.0
-    let guest_nice = if fields.len() > 10 { fields[10].parse::<f64>()? / CLOCKS_PER_SEC } else { 0.0 };
+    let guest_nice = field(10, "guest_nice")?;
 
 
     Ok(TimesStat {
@@ -361,9 +910,9 @@ fn parse_stat_line(line: &str) -> Result<TimesStat, Box<dyn Error>> {
     })
 }
 
-fn finish_cpu_info(stat: &mut InfoStat) {
-    if stat.core_id.len() == 0 {
-        if let Ok(v) = cfs::read_lines(&format!("{}/cpu{}/topology/core_id", SYS_CPU, stat.cpu)) {
+fn finish_cpu_info(ctx: &Context, stat: &mut InfoStat) {
+    if stat.core_id.is_empty() {
+        if let Ok(v) = cfs::read_lines(ctx.sys_path(format!("{}/cpu{}/topology/core_id", SYS_CPU, stat.cpu))) {
             stat.core_id = v[0].clone();
         }
     }
@@ -371,16 +920,16 @@ fn finish_cpu_info(stat: &mut InfoStat) {
     // override the value of c.Mhz with cpufreq/cpuinfo_max_freq regardless
     // of the value from /proc/cpuinfo because we want to report the maximum
     // clock-speed of the CPU for c.Mhz, matching the behaviour of Windows
-    if let Ok(lines) = cfs::read_lines(&format!("{}/cpu{}/cpufreq/cpuinfo_max_freq", SYS_CPU, stat.cpu)) {
+    if let Ok(lines) = cfs::read_lines(ctx.sys_path(format!("{}/cpu{}/cpufreq/cpuinfo_max_freq", SYS_CPU, stat.cpu))) {
         // if we encounter errors below such as there are no cpuinfo_max_freq file,
         // we just ignore. so let Mhz is 0.
-        if lines.len() == 0 {
+        if lines.is_empty() {
             return;
         }
 
         if let Ok(v) = lines[0].parse::<f64>() {
-            if stat.mhz > 9999_000.0 {
-                stat.mhz = v / 1000_000.0;
+            if v > 9_999_000.0 {
+                stat.mhz = v / 1_000_000.0;
             } else {
                 stat.mhz = v / 1000.0;
             }
@@ -388,3 +937,53 @@ fn finish_cpu_info(stat: &mut InfoStat) {
     }
 }
 
+/// riscv_extensions returns the extensions of a RISC-V ISA string, such as
+/// "rv64imafdc_zicsr_zifencei" for i, m, a, f, d, c, zicsr and zifencei.
+/// Versions, as in "rv64i2p1_m2p0_zicsr2p0", are dropped.
+fn riscv_extensions(isa: &str) -> Vec<String> {
+    let isa = isa.to_ascii_lowercase();
+    let mut parts = isa.split('_');
+    let base = parts.next().unwrap_or("");
+    let base = base.trim_start_matches("rv32").trim_start_matches("rv64").trim_start_matches("rv128");
+    let base = base.as_bytes();
+
+    let mut ret = vec![];
+    for (i, &c) in base.iter().enumerate() {
+        // the "p" of a version such as "2p1" separates major and minor
+        let is_version = c.is_ascii_digit() || (c == b'p' && i > 0 && base[i - 1].is_ascii_digit()
+            && base.get(i + 1).is_some_and(u8::is_ascii_digit));
+        if is_version || !c.is_ascii_alphabetic() {
+            continue;
+        }
+
+        if c == b'g' {
+            // "g" is shorthand for the general purpose set
+            ret.extend(["i", "m", "a", "f", "d", "zicsr", "zifencei"].map(String::from));
+        } else {
+            ret.push((c as char).to_string());
+        }
+    }
+    ret.extend(parts.map(strip_riscv_version).filter(|x| !x.is_empty()).map(String::from));
+    ret
+}
+
+/// strip_riscv_version removes the version suffix of an extension name,
+/// e.g. "zicsr" for "zicsr2p0" or "zba1".
+fn strip_riscv_version(ext: &str) -> &str {
+    let name = ext.trim_end_matches(|c: char| c.is_ascii_digit());
+    if name.len() == ext.len() {
+        return ext;
+    }
+    match name.strip_suffix('p') {
+        Some(major) if major.ends_with(|c: char| c.is_ascii_digit()) => {
+            major.trim_end_matches(|c: char| c.is_ascii_digit())
+        }
+        _ => name,
+    }
+}
+
+/// parse_hex parses values such as `0x41` as found in aarch64 cpuinfo.
+fn parse_hex(value: &str) -> Option<usize> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    usize::from_str_radix(digits, 16).ok()
+}
@@ -1,17 +1,34 @@
-use std::error::Error;
-
 #[cfg(target_os = "linux")]
 use crate::cpu::linux::*;
 #[cfg(target_os = "windows")]
 use crate::cpu::windows::*;
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+use crate::cpu::unsupported::*;
+use std::time::Duration;
+
+use crate::context::Context;
+use crate::cpu::percent::*;
+use crate::error::Result;
 
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "windows")]
 mod windows;
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod unsupported;
+#[cfg(target_os = "linux")]
+mod arm;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+mod cpuid;
+mod cpuset;
+mod flags;
+mod percent;
+
+pub use crate::cpu::cpuset::{CpuSet, ParseCpuSetError};
+pub use crate::cpu::flags::{CpuFlag, CpuFlags};
 
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct TimesStat {
     pub cpu: String,
     pub user: f64,
@@ -26,7 +43,212 @@ pub struct TimesStat {
     pub guest_nice: f64,
 }
 
-#[derive(Default, Debug)]
+impl TimesStat {
+    /// total returns the total number of seconds in a TimesStat. On Linux
+    /// guest and guest_nice are already accounted in user and nice, so they
+    /// are not added twice.
+    pub fn total(&self) -> f64 {
+        self.user + self.nice + self.system + self.idle + self.io_wait + self.irq +
+            self.soft_irq + self.steal
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct StatsStat {
+    pub ctx_switches: u64,
+    pub interrupts: u64,
+    pub soft_interrupts: u64,
+    // Always 0 on Linux, which does not count system calls
+    pub syscalls: u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct InterruptStat {
+    // IRQ number, or the name of an architecture specific source such as "LOC"
+    pub irq: String,
+    // Interrupts serviced by each CPU, indexed by CPU number
+    pub per_cpu: Vec<u64>,
+    // Controller, trigger type and device names
+    pub description: String,
+}
+
+impl InterruptStat {
+    pub fn total(&self) -> u64 {
+        self.per_cpu.iter().sum()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SoftInterruptStat {
+    // Softirq type, e.g. "NET_RX" or "TIMER"
+    pub name: String,
+    // Softirqs run by each CPU, indexed by CPU number
+    pub per_cpu: Vec<u64>,
+}
+
+impl SoftInterruptStat {
+    pub fn total(&self) -> u64 {
+        self.per_cpu.iter().sum()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct FreqStat {
+    pub cpu: String,
+    // Frequencies in MHz. min and max are the limits the cpufreq governor
+    // currently works within, not necessarily the hardware limits.
+    pub current: f64,
+    pub min: f64,
+    pub max: f64,
+    pub governor: String,
+    pub available_governors: Vec<String>,
+}
+
+/// SchedStat holds the scheduler statistics of one CPU from /proc/schedstat.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SchedStat {
+    pub cpu: u32,
+    // Time spent running tasks, in nanoseconds
+    pub run_time: u64,
+    // Time tasks spent waiting on the runqueue, in nanoseconds
+    pub wait_time: u64,
+    // Timeslices run on this CPU
+    pub timeslices: u64,
+    // Calls to schedule(), and how many of them left the CPU idle
+    pub sched_count: u64,
+    pub sched_goidle: u64,
+    // Wake-ups issued by this CPU, and how many of them woke a task on it
+    pub ttwu_count: u64,
+    pub ttwu_local: u64,
+    pub domains: Vec<SchedDomainStat>,
+}
+
+/// SchedDomainStat holds the load balancing statistics of one scheduling
+/// domain, as seen from the CPU it is listed under.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SchedDomainStat {
+    // Level of the domain, 0 being the smallest
+    pub level: u32,
+    // e.g. "SMT", "MC" or "PKG"; only reported since schedstat version 17
+    pub name: String,
+    pub cpus: CpuSet,
+    // load_balance() calls, summed over the idle, busy and newly idle cases,
+    // and how many found the domain balanced or failed to move tasks
+    pub lb_count: u64,
+    pub lb_balanced: u64,
+    pub lb_failed: u64,
+    // Active load balancing
+    pub alb_count: u64,
+    pub alb_failed: u64,
+    pub alb_pushed: u64,
+    // Wake-ups of tasks last run on a remote CPU of the domain, and how many
+    // of them were moved to the waking CPU for cache affinity or balance
+    pub ttwu_wake_remote: u64,
+    pub ttwu_move_affine: u64,
+    pub ttwu_move_balance: u64,
+}
+
+/// EffectiveCountsStat is the CPU budget of the current process, as
+/// restricted by its affinity mask and its cgroup.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct EffectiveCountsStat {
+    // CPUs the process may be scheduled on, from sched_getaffinity
+    pub affinity: u32,
+    // CPUs of the cgroup cpuset, if the cpuset controller is enabled
+    pub cpuset: Option<u32>,
+    // CFS bandwidth limit in CPUs (quota / period), if one is set
+    pub quota: Option<f64>,
+    // The smallest of the above, the quota being rounded up; at least 1
+    pub effective: u32,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct VulnerabilityStat {
+    // File name under /sys/devices/system/cpu/vulnerabilities, e.g. "spectre_v2"
+    pub name: String,
+    pub status: VulnerabilityStatus,
+    // Text following a Mitigated status, e.g. "PTI" for "Mitigation: PTI"
+    pub mitigation: String,
+    // The line reported by the kernel, as is
+    pub description: String,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VulnerabilityStatus {
+    NotAffected,
+    Mitigated,
+    Vulnerable,
+    // The kernel cannot tell, e.g. because it depends on the hypervisor
+    #[default]
+    Unknown,
+}
+
+/// CpuSetKind selects one of the CPU lists the kernel maintains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuSetKind {
+    // CPUs currently available to the scheduler
+    Online,
+    // Present CPUs that were hot-unplugged
+    Offline,
+    // CPUs that can ever be brought online, including future hot-plugged ones
+    Possible,
+    // CPUs physically present in the system
+    Present,
+    // CPUs removed from scheduler load balancing by isolcpus=
+    Isolated,
+    // CPUs running without the periodic tick, from nohz_full=
+    NohzFull,
+}
+
+/// TopologyStat describes how logical CPUs are laid out into packages
+/// (sockets), dies and cores, which NUMA nodes they belong to, and the caches
+/// they share.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TopologyStat {
+    pub packages: Vec<PackageStat>,
+    pub numa_nodes: Vec<NumaNodeStat>,
+    // Every distinct cache, once, with the CPUs sharing it
+    pub caches: Vec<CacheStat>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PackageStat {
+    pub id: i32,
+    pub dies: Vec<DieStat>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct DieStat {
+    pub id: i32,
+    pub cores: Vec<CoreStat>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CoreStat {
+    pub id: i32,
+    // Logical CPUs (SMT siblings) of this core
+    pub cpus: CpuSet,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct NumaNodeStat {
+    pub id: u32,
+    pub cpus: CpuSet,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CacheStat {
+    pub level: u32,
+    // "Data", "Instruction" or "Unified"
+    pub cache_type: String,
+    // Size in bytes
+    pub size: u64,
+    pub line_size: u32,
+    pub ways_of_associativity: u32,
+    pub shared_cpus: CpuSet,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct InfoStat {
     pub cpu: i32,
     pub vendor_id: String,
@@ -39,27 +261,199 @@ pub struct InfoStat {
     pub model_name: String,
     pub mhz: f64,
     pub cache_size: i32,
-    pub flags: Vec<String>,
+    pub flags: CpuFlags,
     pub microcode: String,
+    // Only known for hybrid x86 parts, and only through cpuid_infos
+    pub core_type: CoreType,
+}
+
+/// CoreType tells the performance and efficiency cores of hybrid CPUs apart.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreType {
+    #[default]
+    Unknown,
+    // P-core
+    Performance,
+    // E-core
+    Efficiency,
 }
 
 
-pub fn times(percpu: bool) -> Result<Vec<TimesStat>, Box<dyn Error>> {
-    return if percpu {
-        per_cpu_times()
+pub fn times(percpu: bool) -> Result<Vec<TimesStat>> {
+    times_with_context(&Context::default(), percpu)
+}
+
+pub fn times_with_context(ctx: &Context, percpu: bool) -> Result<Vec<TimesStat>> {
+    if percpu {
+        per_cpu_times(ctx)
     } else {
-        total_cpu_times()
-    };
+        total_cpu_times(ctx)
+    }
+}
+
+pub fn infos() -> Result<Vec<InfoStat>> {
+    infos_with_context(&Context::default())
+}
+
+pub fn infos_with_context(ctx: &Context) -> Result<Vec<InfoStat>> {
+    all_infos(ctx)
+}
+
+/// cpuid_infos is like infos, but decodes the CPUID instruction on every CPU
+/// the process may run on instead of reading /proc/cpuinfo, so it works where
+/// /proc is unavailable or masked. family, model and stepping are the
+/// numeric display values, and core_type is set on hybrid CPUs. Fields CPUID
+/// cannot provide, such as microcode, are left empty.
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid_infos() -> Result<Vec<InfoStat>> {
+    cpuid_cpu_infos()
 }
 
-pub fn infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
-    all_infos()
+pub fn counts(logical: bool) -> Result<u32> {
+    counts_with_context(&Context::default(), logical)
 }
 
-pub fn counts(logical: bool) -> Result<u32, Box<dyn Error>> {
-    return if logical {
-        logical_counts()
+pub fn counts_with_context(ctx: &Context, logical: bool) -> Result<u32> {
+    if logical {
+        logical_counts(ctx)
     } else {
-        physical_counts()
-    };
+        physical_counts(ctx)
+    }
+}
+
+/// stats returns the number of context switches, interrupts and soft
+/// interrupts since boot.
+pub fn stats() -> Result<StatsStat> {
+    stats_with_context(&Context::default())
+}
+
+pub fn stats_with_context(ctx: &Context) -> Result<StatsStat> {
+    cpu_stats(ctx)
+}
+
+/// interrupts returns the per-CPU counters of every interrupt source.
+pub fn interrupts() -> Result<Vec<InterruptStat>> {
+    interrupts_with_context(&Context::default())
+}
+
+pub fn interrupts_with_context(ctx: &Context) -> Result<Vec<InterruptStat>> {
+    all_interrupts(ctx)
+}
+
+/// soft_interrupts returns the per-CPU counters of every softirq type.
+pub fn soft_interrupts() -> Result<Vec<SoftInterruptStat>> {
+    soft_interrupts_with_context(&Context::default())
+}
+
+pub fn soft_interrupts_with_context(ctx: &Context) -> Result<Vec<SoftInterruptStat>> {
+    all_soft_interrupts(ctx)
+}
+
+/// sched_stats returns the per-CPU scheduler statistics: time spent running
+/// and waiting on the runqueue, and load balancing per scheduling domain.
+pub fn sched_stats() -> Result<Vec<SchedStat>> {
+    sched_stats_with_context(&Context::default())
+}
+
+pub fn sched_stats_with_context(ctx: &Context) -> Result<Vec<SchedStat>> {
+    cpu_sched_stats(ctx)
+}
+
+/// effective_counts returns the number of CPUs the current process can
+/// actually use, accounting for its affinity mask and the cpuset and CPU
+/// quota of its cgroup (v1 or v2), as in a Kubernetes pod.
+pub fn effective_counts() -> Result<EffectiveCountsStat> {
+    effective_counts_with_context(&Context::default())
+}
+
+pub fn effective_counts_with_context(ctx: &Context) -> Result<EffectiveCountsStat> {
+    effective_cpu_counts(ctx)
+}
+
+/// vulnerabilities returns the side-channel vulnerabilities the kernel
+/// knows about and whether the CPU is affected or mitigated.
+pub fn vulnerabilities() -> Result<Vec<VulnerabilityStat>> {
+    vulnerabilities_with_context(&Context::default())
+}
+
+pub fn vulnerabilities_with_context(ctx: &Context) -> Result<Vec<VulnerabilityStat>> {
+    cpu_vulnerabilities(ctx)
+}
+
+/// cpu_set returns one of the CPU lists of /sys/devices/system/cpu, e.g.
+/// the online CPUs or the ones isolated from the scheduler.
+pub fn cpu_set(kind: CpuSetKind) -> Result<CpuSet> {
+    cpu_set_with_context(&Context::default(), kind)
+}
+
+pub fn cpu_set_with_context(ctx: &Context, kind: CpuSetKind) -> Result<CpuSet> {
+    cpu_list(ctx, kind)
+}
+
+/// topology returns the package, die, core and NUMA node layout of the
+/// online CPUs and their caches.
+pub fn topology() -> Result<TopologyStat> {
+    topology_with_context(&Context::default())
+}
+
+pub fn topology_with_context(ctx: &Context) -> Result<TopologyStat> {
+    cpu_topology(ctx)
+}
+
+/// freq returns the current, min and max frequency and the cpufreq governor
+/// either per CPU or, averaged, for the whole system. Where cpufreq is not
+/// available the current frequency falls back to "cpu MHz" of /proc/cpuinfo.
+pub fn freq(percpu: bool) -> Result<Vec<FreqStat>> {
+    freq_with_context(&Context::default(), percpu)
+}
+
+pub fn freq_with_context(ctx: &Context, percpu: bool) -> Result<Vec<FreqStat>> {
+    let freqs = cpu_freqs(ctx)?;
+    if percpu || freqs.is_empty() {
+        return Ok(freqs);
+    }
+
+    let n = freqs.len() as f64;
+    Ok(vec![FreqStat {
+        cpu: "cpu-total".to_string(),
+        current: freqs.iter().map(|x| x.current).sum::<f64>() / n,
+        min: freqs.iter().map(|x| x.min).sum::<f64>() / n,
+        max: freqs.iter().map(|x| x.max).sum::<f64>() / n,
+        governor: common_value(&freqs, |x| &x.governor),
+        available_governors: common_value(&freqs, |x| &x.available_governors),
+    }])
+}
+
+/// common_value returns the value shared by all CPUs, or the default if they differ.
+fn common_value<T: Clone + Default + PartialEq>(freqs: &[FreqStat], f: impl Fn(&FreqStat) -> &T) -> T {
+    let first = f(&freqs[0]);
+    if freqs.iter().all(|x| f(x) == first) { first.clone() } else { T::default() }
+}
+
+/// percent calculates the percentage of cpu used either per CPU or combined,
+/// paired with the name of the CPU as in TimesStat::cpu ("cpu-total" when
+/// combined), since CPUs can be plugged or unplugged between samples.
+/// If an interval of 0 is given it will compare the current cpu times against
+/// the last call, so the first call returns 0 for every CPU.
+pub fn percent(interval: Duration, percpu: bool) -> Result<Vec<(String, f64)>> {
+    percent_with_context(&Context::default(), interval, percpu)
+}
+
+pub fn percent_with_context(ctx: &Context, interval: Duration, percpu: bool) -> Result<Vec<(String, f64)>> {
+    let last = if percpu { &LAST_PER_CPU_TIMES } else { &LAST_CPU_TIMES };
+    let (t1, t2) = sample(ctx, interval, percpu, last)?;
+    Ok(calculate_all_busy(&t1, &t2))
+}
+
+/// times_percent is like times, but every field is expressed as the
+/// percentage of the elapsed time spent in it, either per CPU or combined.
+/// The interval is handled the same way as in percent.
+pub fn times_percent(interval: Duration, percpu: bool) -> Result<Vec<TimesStat>> {
+    times_percent_with_context(&Context::default(), interval, percpu)
+}
+
+pub fn times_percent_with_context(ctx: &Context, interval: Duration, percpu: bool) -> Result<Vec<TimesStat>> {
+    let last = if percpu { &LAST_PER_CPU_TIMES_PERCENT } else { &LAST_CPU_TIMES_PERCENT };
+    let (t1, t2) = sample(ctx, interval, percpu, last)?;
+    Ok(calculate_all_times_percent(&t1, &t2))
 }
@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::context::Context;
+use crate::cpu::{times_with_context, TimesStat};
+use crate::error::Result;
+
+/// The samples taken by the previous `percent` and `times_percent` calls, used
+/// when they are called with a zero interval. psutil keeps the aggregate and
+/// per-CPU samples of both functions apart so that alternating between them
+/// does not reset either.
+pub static LAST_CPU_TIMES: Mutex<Vec<TimesStat>> = Mutex::new(Vec::new());
+pub static LAST_PER_CPU_TIMES: Mutex<Vec<TimesStat>> = Mutex::new(Vec::new());
+pub static LAST_CPU_TIMES_PERCENT: Mutex<Vec<TimesStat>> = Mutex::new(Vec::new());
+pub static LAST_PER_CPU_TIMES_PERCENT: Mutex<Vec<TimesStat>> = Mutex::new(Vec::new());
+
+/// sample returns two cpu times samples taken `interval` apart, or the one
+/// stored in `last` and a fresh one if the interval is zero. The fresh sample
+/// is stored in `last` in both cases.
+pub fn sample(ctx: &Context, interval: Duration, percpu: bool, last: &Mutex<Vec<TimesStat>>)
+              -> Result<(Vec<TimesStat>, Vec<TimesStat>)> {
+    let t1 = if interval.is_zero() {
+        None
+    } else {
+        let t1 = times_with_context(ctx, percpu)?;
+        thread::sleep(interval);
+        Some(t1)
+    };
+
+    let t2 = times_with_context(ctx, percpu)?;
+    let previous = swap_last(last, &t2);
+    Ok((t1.unwrap_or(previous), t2))
+}
+
+fn swap_last(last: &Mutex<Vec<TimesStat>>, times: &[TimesStat]) -> Vec<TimesStat> {
+    let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *last, times.to_vec())
+}
+
+/// calculate_all_busy returns the name and busy percentage of every CPU in
+/// `t2`, matching CPUs by name so that a CPU unplugged or plugged between both
+/// samples does not shift the others. CPUs missing from `t1` report 0.
+pub fn calculate_all_busy(t1: &[TimesStat], t2: &[TimesStat]) -> Vec<(String, f64)> {
+    t2.iter().map(|b| {
+        let busy = match t1.iter().find(|a| a.cpu == b.cpu) {
+            Some(a) => calculate_busy(a, b),
+            None => 0.0,
+        };
+        (b.cpu.clone(), busy)
+    }).collect()
+}
+
+/// calculate_all_times_percent expresses every field of the deltas between
+/// `t1` and `t2` as a percentage of the elapsed time, matching CPUs by name.
+pub fn calculate_all_times_percent(t1: &[TimesStat], t2: &[TimesStat]) -> Vec<TimesStat> {
+    t2.iter().map(|b| {
+        match t1.iter().find(|a| a.cpu == b.cpu) {
+            Some(a) => calculate_times_percent(a, b),
+            None => TimesStat { cpu: b.cpu.clone(), ..Default::default() },
+        }
+    }).collect()
+}
+
+fn calculate_times_percent(t1: &TimesStat, t2: &TimesStat) -> TimesStat {
+    let delta = deltas(t1, t2);
+    let all = delta.total();
+    if all <= 0.0 {
+        return TimesStat { cpu: delta.cpu, ..Default::default() };
+    }
+
+    let p = |v: f64| (v / all * 100.0).clamp(0.0, 100.0);
+    TimesStat {
+        cpu: delta.cpu.clone(),
+        user: p(delta.user),
+        nice: p(delta.nice),
+        system: p(delta.system),
+        idle: p(delta.idle),
+        io_wait: p(delta.io_wait),
+        irq: p(delta.irq),
+        soft_irq: p(delta.soft_irq),
+        steal: p(delta.steal),
+        guest: p(delta.guest),
+        guest_nice: p(delta.guest_nice),
+    }
+}
+
+fn calculate_busy(t1: &TimesStat, t2: &TimesStat) -> f64 {
+    let delta = deltas(t1, t2);
+    let all = delta.total();
+    if all <= 0.0 {
+        return 0.0;
+    }
+
+    let busy = all - delta.idle - delta.io_wait;
+    (busy / all * 100.0).clamp(0.0, 100.0)
+}
+
+/// deltas subtracts `t1` from `t2` field by field. A field going backwards,
+/// as happens when a counter wraps or the kernel resets it, counts as 0.
+fn deltas(t1: &TimesStat, t2: &TimesStat) -> TimesStat {
+    let d = |a: f64, b: f64| (b - a).max(0.0);
+    TimesStat {
+        cpu: t2.cpu.clone(),
+        user: d(t1.user, t2.user),
+        nice: d(t1.nice, t2.nice),
+        system: d(t1.system, t2.system),
+        idle: d(t1.idle, t2.idle),
+        io_wait: d(t1.io_wait, t2.io_wait),
+        irq: d(t1.irq, t2.irq),
+        soft_irq: d(t1.soft_irq, t2.soft_irq),
+        steal: d(t1.steal, t2.steal),
+        guest: d(t1.guest, t2.guest),
+        guest_nice: d(t1.guest_nice, t2.guest_nice),
+    }
+}
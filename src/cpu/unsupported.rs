@@ -0,0 +1,67 @@
+use crate::context::Context;
+use crate::cpu::{
+    CpuSet, CpuSetKind, EffectiveCountsStat, FreqStat, InfoStat, InterruptStat, SchedStat, SoftInterruptStat, StatsStat,
+    TimesStat, TopologyStat, VulnerabilityStat,
+};
+use crate::error::{Error, Result};
+
+pub fn total_cpu_times(_ctx: &Context) -> Result<Vec<TimesStat>> {
+    Err(Error::Unsupported("cpu::times"))
+}
+
+pub fn per_cpu_times(_ctx: &Context) -> Result<Vec<TimesStat>> {
+    Err(Error::Unsupported("cpu::times"))
+}
+
+pub fn all_infos(_ctx: &Context) -> Result<Vec<InfoStat>> {
+    Err(Error::Unsupported("cpu::infos"))
+}
+
+pub fn logical_counts(_ctx: &Context) -> Result<u32> {
+    Err(Error::Unsupported("cpu::counts"))
+}
+
+pub fn physical_counts(_ctx: &Context) -> Result<u32> {
+    Err(Error::Unsupported("cpu::counts"))
+}
+
+pub fn cpu_stats(_ctx: &Context) -> Result<StatsStat> {
+    Err(Error::Unsupported("cpu::stats"))
+}
+
+pub fn all_interrupts(_ctx: &Context) -> Result<Vec<InterruptStat>> {
+    Err(Error::Unsupported("cpu::interrupts"))
+}
+
+pub fn all_soft_interrupts(_ctx: &Context) -> Result<Vec<SoftInterruptStat>> {
+    Err(Error::Unsupported("cpu::soft_interrupts"))
+}
+
+pub fn cpu_freqs(_ctx: &Context) -> Result<Vec<FreqStat>> {
+    Err(Error::Unsupported("cpu::freq"))
+}
+
+pub fn cpu_topology(_ctx: &Context) -> Result<TopologyStat> {
+    Err(Error::Unsupported("cpu::topology"))
+}
+
+pub fn cpu_list(_ctx: &Context, _kind: CpuSetKind) -> Result<CpuSet> {
+    Err(Error::Unsupported("cpu::cpu_set"))
+}
+
+pub fn cpu_sched_stats(_ctx: &Context) -> Result<Vec<SchedStat>> {
+    Err(Error::Unsupported("cpu::sched_stats"))
+}
+
+pub fn effective_cpu_counts(_ctx: &Context) -> Result<EffectiveCountsStat> {
+    Err(Error::Unsupported("cpu::effective_counts"))
+}
+
+pub fn cpu_vulnerabilities(_ctx: &Context) -> Result<Vec<VulnerabilityStat>> {
+    Err(Error::Unsupported("cpu::vulnerabilities"))
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid_cpu_infos() -> Result<Vec<InfoStat>> {
+    Err(Error::Unsupported("cpu::cpuid_infos"))
+}
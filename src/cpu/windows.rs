@@ -1,6 +1,5 @@
 use std::{io, mem};
 use std::cmp::min;
-use std::error::Error;
 
 use windows::core::{BSTR, VARIANT, w};
 use windows::Win32::System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance, COINIT_MULTITHREADED, CoInitializeEx, CoInitializeSecurity, EOAC_NONE, RPC_C_AUTHN_LEVEL_DEFAULT, RPC_C_IMP_LEVEL_IMPERSONATE};
@@ -11,10 +10,17 @@ use windows_sys::Win32::System::SystemInformation::{GetSystemInfo, SYSTEM_INFO};
 use windows_sys::Win32::System::Threading::{ALL_PROCESSOR_GROUPS, GetActiveProcessorCount, GetSystemTimes};
 
 use crate::common::binary::{little_endian_u32, little_endian_u64};
-use crate::cpu::{InfoStat, TimesStat};
+use crate::common::parse::parse_field;
+use crate::context::Context;
+use crate::cpu::{
+    CpuSet, CpuSetKind, EffectiveCountsStat, FreqStat, InfoStat, InterruptStat, SchedStat, SoftInterruptStat, StatsStat,
+    TimesStat, TopologyStat, VulnerabilityStat,
+};
+use crate::error::{Error, Result};
 
 const DEFAULT_CPU_NUM: u32 = 1024;
 const CLOCKS_PER_SEC: f64 = 10000000.0;
+const WIN32_PROCESSOR: &str = "Win32_Processor";
 
 #[derive(Debug)]
 struct Win32SystemProcessorPerformanceInformation {
@@ -27,7 +33,7 @@ struct Win32SystemProcessorPerformanceInformation {
 }
 
 
-pub fn total_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
+pub fn total_cpu_times(_ctx: &Context) -> Result<Vec<TimesStat>> {
     let mut lpidletime = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
     let mut lpkerneltime = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
     let mut lpusertime = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
@@ -37,7 +43,7 @@ pub fn total_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
     };
 
     if ret == 0 {
-        return Err(io::Error::last_os_error().into());
+        return Err(Error::Os { call: "GetSystemTimes", source: io::Error::last_os_error() });
     }
 
     let lot = 0.0000001;
@@ -50,8 +56,8 @@ pub fn total_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
     Ok(vec![TimesStat { cpu: "total".to_string(), user, system, idle, ..Default::default() }])
 }
 
-pub fn per_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
-    let performances = performance_info()?;
+pub fn per_cpu_times(ctx: &Context) -> Result<Vec<TimesStat>> {
+    let performances = performance_info(ctx)?;
     let mut result = Vec::with_capacity(performances.len());
 
     for (i, p) in performances.iter().enumerate() {
@@ -68,7 +74,7 @@ pub fn per_cpu_times() -> Result<Vec<TimesStat>, Box<dyn Error>> {
     Ok(result)
 }
 
-pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
+pub fn all_infos(_ctx: &Context) -> Result<Vec<InfoStat>> {
     let mut ret = Vec::new();
 
     unsafe {
@@ -133,9 +139,9 @@ pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
                     vendor_id: manufacturer.to_string(),
                     family: family.to_string(),
                     physical_id: processor_id.to_string(),
-                    cores: number_of_logical_processors.to_string().parse::<i32>()?,
+                    cores: parse_field(&number_of_logical_processors.to_string(), WIN32_PROCESSOR, 0, "NumberOfLogicalProcessors")?,
                     model_name: name.to_string(),
-                    mhz: max_clock_speed.to_string().parse::<f64>()?,
+                    mhz: parse_field(&max_clock_speed.to_string(), WIN32_PROCESSOR, 0, "MaxClockSpeed")?,
                     ..Default::default()
                 });
 
@@ -149,7 +155,7 @@ pub fn all_infos() -> Result<Vec<InfoStat>, Box<dyn Error>> {
     Ok(ret)
 }
 
-pub fn logical_counts() -> Result<u32, Box<dyn Error>> {
+pub fn logical_counts(_ctx: &Context) -> Result<u32> {
     unsafe {
         let ret = GetActiveProcessorCount(ALL_PROCESSOR_GROUPS);
         if ret != 0 {
@@ -162,7 +168,7 @@ pub fn logical_counts() -> Result<u32, Box<dyn Error>> {
     }
 }
 
-pub fn physical_counts() -> Result<u32, Box<dyn Error>> {
+pub fn physical_counts(_ctx: &Context) -> Result<u32> {
     unsafe {
         CoInitializeEx(None, COINIT_MULTITHREADED).ok()?;
 
@@ -202,7 +208,7 @@ pub fn physical_counts() -> Result<u32, Box<dyn Error>> {
 
                 row.Get(w!("NumberOfCores"), 0, &mut number_of_cores, None, None)?;
 
-                cpu += number_of_cores.to_string().parse::<u32>().unwrap();
+                cpu += parse_field::<u32, _>(&number_of_cores.to_string(), WIN32_PROCESSOR, 0, "NumberOfCores")?;
             } else {
                 return Ok(cpu);
             }
@@ -210,8 +216,49 @@ pub fn physical_counts() -> Result<u32, Box<dyn Error>> {
     }
 }
 
-fn performance_info() -> Result<Vec<Win32SystemProcessorPerformanceInformation>, Box<dyn Error>> {
-    let cpu = logical_counts().unwrap_or(DEFAULT_CPU_NUM);
+pub fn cpu_stats(_ctx: &Context) -> Result<StatsStat> {
+    Err(Error::Unsupported("cpu::stats"))
+}
+
+pub fn all_interrupts(_ctx: &Context) -> Result<Vec<InterruptStat>> {
+    Err(Error::Unsupported("cpu::interrupts"))
+}
+
+pub fn all_soft_interrupts(_ctx: &Context) -> Result<Vec<SoftInterruptStat>> {
+    Err(Error::Unsupported("cpu::soft_interrupts"))
+}
+
+pub fn cpu_freqs(_ctx: &Context) -> Result<Vec<FreqStat>> {
+    Err(Error::Unsupported("cpu::freq"))
+}
+
+pub fn cpu_topology(_ctx: &Context) -> Result<TopologyStat> {
+    Err(Error::Unsupported("cpu::topology"))
+}
+
+pub fn cpu_list(_ctx: &Context, _kind: CpuSetKind) -> Result<CpuSet> {
+    Err(Error::Unsupported("cpu::cpu_set"))
+}
+
+pub fn cpu_sched_stats(_ctx: &Context) -> Result<Vec<SchedStat>> {
+    Err(Error::Unsupported("cpu::sched_stats"))
+}
+
+pub fn effective_cpu_counts(_ctx: &Context) -> Result<EffectiveCountsStat> {
+    Err(Error::Unsupported("cpu::effective_counts"))
+}
+
+pub fn cpu_vulnerabilities(_ctx: &Context) -> Result<Vec<VulnerabilityStat>> {
+    Err(Error::Unsupported("cpu::vulnerabilities"))
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn cpuid_cpu_infos() -> Result<Vec<InfoStat>> {
+    Err(Error::Unsupported("cpu::cpuid_infos"))
+}
+
+fn performance_info(ctx: &Context) -> Result<Vec<Win32SystemProcessorPerformanceInformation>> {
+    let cpu = logical_counts(ctx).unwrap_or(DEFAULT_CPU_NUM);
     let win32system_processor_performance_information_size = mem::size_of::<Win32SystemProcessorPerformanceInformation>();
     let length: u32 = win32system_processor_performance_information_size as u32 * cpu;
     let mut buffer: Vec<u8> = vec![0u8; length as usize];
@@ -219,7 +266,10 @@ fn performance_info() -> Result<Vec<Win32SystemProcessorPerformanceInformation>,
     unsafe {
         let status = NtQuerySystemInformation(SystemProcessorPerformanceInformation, buffer.as_mut_ptr() as _, length, &mut ret);
         if status != 0 {
-            return Err(Box::new(io::Error::new(io::ErrorKind::Other, format!("call to NtQuerySystemInformation returned {}.", status))));
+            return Err(Error::Os {
+                call: "NtQuerySystemInformation",
+                source: io::Error::new(io::ErrorKind::Other, format!("call to NtQuerySystemInformation returned {}.", status)),
+            });
         }
     }
 
@@ -1,11 +1,31 @@
-use std::error::Error;
-use std::ffi::CString;
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
 use std::io;
+use std::path::{Component, Path, PathBuf};
 
-use crate::disk::UsageStat;
+use crate::common::fs as cfs;
+use crate::common::parse::parse_field;
+use crate::context::Context;
+use crate::disk::{BlockDeviceKind, BlockDeviceStat, IOCountersStat, PartitionStat, UsageStat};
+use crate::error::{Error, Result};
 
-pub fn get_usage(path: &str) -> Result<UsageStat, Box<dyn Error>> {
-    let stat = statfs(path)?;
+const PROC_MOUNTINFO: &str = "self/mountinfo";
+const PROC_MOUNTS: &str = "mounts";
+const PROC_FILESYSTEMS: &str = "filesystems";
+const PROC_DISKSTATS: &str = "diskstats";
+const ETC_MTAB: &str = "mtab";
+const SYS_DEV_BLOCK: &str = "dev/block";
+const SYS_BLOCK: &str = "block";
+const SYS_CLASS_BLOCK: &str = "class/block";
+const DEV_DISK_BY_LABEL: &str = "disk/by-label";
+const RUN_UDEV_DATA: &str = "udev/data";
+
+// /proc/diskstats counts sectors of 512 bytes, whatever the logical or
+// hardware sector size of the device, see Documentation/block/stat.rst
+const SECTOR_SIZE: u64 = 512;
+
+pub fn get_usage(path: &str) -> Result<UsageStat> {
+    let stat = statfs(path).map_err(|e| Error::io(path, e))?;
 
 
     let mut ret = UsageStat {
@@ -40,6 +60,451 @@ pub fn get_usage(path: &str) -> Result<UsageStat, Box<dyn Error>> {
     Ok(ret)
 }
 
+pub fn get_io_counters(ctx: &Context, names: &[&str]) -> Result<Vec<IOCountersStat>> {
+    let path = ctx.proc_path(PROC_DISKSTATS);
+    let lines = cfs::read_lines(&path)?;
+    let labels = disk_labels(ctx)?;
+    let mut ret = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        // major minor name, then 11 fields, 15 since Linux 4.18 and 17 since 5.5
+        let fields = line.split_ascii_whitespace().collect::<Vec<&str>>();
+        if fields.len() < 14 {
+            return Err(Error::parse(&path, i + 1, "diskstats", "too few fields"));
+        }
+
+        let name = fields[2];
+        if !names.is_empty() && !names.contains(&name) {
+            continue;
+        }
+        let udev = udev_properties(ctx, &format!("{}:{}", fields[0], fields[1]))?;
+
+        let field = |n: usize| -> Result<u64> {
+            match fields.get(n) {
+                Some(v) => parse_field(v, &path, i + 1, name),
+                None => Ok(0),
+            }
+        };
+
+        ret.push(IOCountersStat {
+            read_count: field(3)?,
+            merged_read_count: field(4)?,
+            read_bytes: field(5)? * SECTOR_SIZE,
+            read_time: field(6)?,
+            write_count: field(7)?,
+            merged_write_count: field(8)?,
+            write_bytes: field(9)? * SECTOR_SIZE,
+            write_time: field(10)?,
+            iops_in_progress: field(11)?,
+            io_time: field(12)?,
+            weighted_io: field(13)?,
+            discard_count: field(14)?,
+            merged_discard_count: field(15)?,
+            discard_bytes: field(16)? * SECTOR_SIZE,
+            discard_time: field(17)?,
+            flush_count: field(18)?,
+            flush_time: field(19)?,
+            name: name.to_string(),
+            serial_number: serial_number(ctx, name, &udev)?,
+            label: label(name, &udev, &labels),
+        });
+    }
+
+    Ok(ret)
+}
+
+pub fn get_serial_number(ctx: &Context, name: &str) -> Result<String> {
+    let name = name.strip_prefix("/dev/").unwrap_or(name);
+    serial_number(ctx, name, &device_udev_properties(ctx, name)?)
+}
+
+pub fn get_label(ctx: &Context, name: &str) -> Result<String> {
+    let name = name.strip_prefix("/dev/").unwrap_or(name);
+    Ok(label(name, &device_udev_properties(ctx, name)?, &disk_labels(ctx)?))
+}
+
+/// serial_number returns the serial number of the block device `name`,
+/// `udev` being its udev properties.
+fn serial_number(ctx: &Context, name: &str, udev: &HashMap<String, String>) -> Result<String> {
+    if let Some(serial) = udev.get("ID_SERIAL") {
+        return Ok(serial.clone());
+    }
+
+    // without udev, e.g. in a container, some drivers still expose it
+    Ok(read_attr(&ctx.sys_path(format!("{}/{}/device/serial", SYS_BLOCK, name)))?.unwrap_or_default())
+}
+
+/// label returns the label of the filesystem on the block device `name`,
+/// `udev` being its udev properties and `labels` the result of disk_labels.
+fn label(name: &str, udev: &HashMap<String, String>, labels: &HashMap<String, String>) -> String {
+    udev.get("ID_FS_LABEL").or_else(|| labels.get(name)).cloned().unwrap_or_default()
+}
+
+/// disk_labels maps device names to filesystem labels using
+/// /dev/disk/by-label, whose entries link <label> to ../../<name>.
+fn disk_labels(ctx: &Context) -> Result<HashMap<String, String>> {
+    let dir = ctx.dev_path(DEV_DISK_BY_LABEL);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(Error::io(&dir, e)),
+    };
+
+    let mut ret = HashMap::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io(&dir, e))?;
+        if let Ok(target) = std::fs::read_link(entry.path()) {
+            if let Some(name) = target.file_name() {
+                let label = unescape_udev(&entry.file_name().to_string_lossy());
+                ret.insert(name.to_string_lossy().into_owned(), label);
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// device_udev_properties returns the udev properties of the block device
+/// `name`, none if there is no such device.
+fn device_udev_properties(ctx: &Context, name: &str) -> Result<HashMap<String, String>> {
+    match read_attr(&ctx.sys_path(format!("{}/{}/dev", SYS_CLASS_BLOCK, name)))? {
+        Some(dev) => udev_properties(ctx, &dev),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// udev_properties returns the properties of the block device `dev`
+/// (major:minor) from the udev database, whose records look like
+///
+/// S:disk/by-id/nvme-Samsung_SSD_970_EVO_Plus_1TB_S4EWNX0R123456
+/// E:ID_SERIAL=Samsung_SSD_970_EVO_Plus_1TB_S4EWNX0R123456
+fn udev_properties(ctx: &Context, dev: &str) -> Result<HashMap<String, String>> {
+    let lines = match cfs::read_lines(ctx.run_path(format!("{}/b{}", RUN_UDEV_DATA, dev))) {
+        Ok(v) => v,
+        Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(lines.iter()
+        .filter_map(|line| line.strip_prefix("E:")?.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect())
+}
+
+/// unescape_udev decodes the \xNN escapes udev uses in /dev/disk link names,
+/// e.g. "backup\x20disk".
+fn unescape_udev(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b'x') {
+            let hex = s.get(i + 2..i + 4).and_then(|x| u8::from_str_radix(x, 16).ok());
+            if let Some(b) = hex {
+                ret.push(b);
+                i += 4;
+                continue;
+            }
+        }
+        ret.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+pub fn get_partitions(ctx: &Context, all: bool) -> Result<Vec<PartitionStat>> {
+    let mut ret = match cfs::read_lines(ctx.proc_path(PROC_MOUNTINFO)) {
+        Ok(lines) => parse_mountinfo(ctx, &lines, &ctx.proc_path(PROC_MOUNTINFO))?,
+        // mountinfo only exists since Linux 2.6.26
+        Err(_) => match cfs::read_lines(ctx.proc_path(PROC_MOUNTS)) {
+            Ok(lines) => parse_mounts(&lines, &ctx.proc_path(PROC_MOUNTS))?,
+            Err(_) => parse_mounts(&cfs::read_lines(ctx.etc_path(ETC_MTAB))?, &ctx.etc_path(ETC_MTAB))?,
+        },
+    };
+
+    for partition in ret.iter_mut() {
+        // /dev/mapper entries are symlinks to the dm-N devices
+        if let Some(name) = partition.device.strip_prefix("/dev/mapper/") {
+            if let Ok(target) = std::fs::read_link(ctx.dev_path("mapper").join(name)) {
+                partition.device = normalize(&Path::new("/dev/mapper").join(target)).to_string_lossy().into_owned();
+            }
+        }
+    }
+
+    if !all {
+        let filesystems = block_filesystems(ctx)?;
+        ret.retain(|x| x.device != "none" && filesystems.contains(&x.fstype));
+    }
+
+    Ok(ret)
+}
+
+/// parse_mountinfo parses /proc/<pid>/mountinfo, whose lines look like
+///
+/// 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+///
+/// i.e. mount ID, parent ID, major:minor, root, mount point, mount options,
+/// optional fields terminated by "-", then fs type, source and superblock options.
+fn parse_mountinfo(ctx: &Context, lines: &[String], path: &Path) -> Result<Vec<PartitionStat>> {
+    let mut ret = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let fields = line.split_ascii_whitespace().collect::<Vec<&str>>();
+        let Some(sep) = fields.iter().position(|x| *x == "-") else {
+            return Err(Error::parse(path, i + 1, "separator", "no optional fields separator"));
+        };
+        if sep < 6 || fields.len() < sep + 3 {
+            return Err(Error::parse(path, i + 1, "mount", "too few fields"));
+        }
+
+        let mut opts = fields[5].split(',').map(String::from).collect::<Vec<String>>();
+        if let Some(super_opts) = fields.get(sep + 3) {
+            for opt in super_opts.split(',') {
+                if !opts.iter().any(|x| x == opt) {
+                    opts.push(opt.to_string());
+                }
+            }
+        }
+
+        let mut device = unescape(fields[sep + 2]);
+        // /dev/root is an alias of the root device given on the kernel
+        // command line, sysfs knows the actual name
+        if device == "/dev/root" {
+            if let Ok(target) = std::fs::read_link(ctx.sys_path(SYS_DEV_BLOCK).join(fields[2])) {
+                if let Some(name) = target.file_name() {
+                    device = format!("/dev/{}", name.to_string_lossy());
+                }
+            }
+        }
+
+        ret.push(PartitionStat {
+            device,
+            mountpoint: unescape(fields[4]),
+            fstype: unescape(fields[sep + 1]),
+            opts,
+        });
+    }
+
+    Ok(ret)
+}
+
+/// parse_mounts parses the fstab-like format of /proc/mounts and /etc/mtab.
+fn parse_mounts(lines: &[String], path: &Path) -> Result<Vec<PartitionStat>> {
+    let mut ret = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = line.split_ascii_whitespace().collect::<Vec<&str>>();
+        if fields.len() < 4 {
+            return Err(Error::parse(path, i + 1, "mount", "too few fields"));
+        }
+
+        ret.push(PartitionStat {
+            device: unescape(fields[0]),
+            mountpoint: unescape(fields[1]),
+            fstype: unescape(fields[2]),
+            opts: fields[3].split(',').map(String::from).collect(),
+        });
+    }
+
+    Ok(ret)
+}
+
+/// block_filesystems returns the filesystem types of /proc/filesystems that
+/// need a block device, i.e. those not marked "nodev".
+fn block_filesystems(ctx: &Context) -> Result<Vec<String>> {
+    let lines = cfs::read_lines(ctx.proc_path(PROC_FILESYSTEMS))?;
+    Ok(lines.iter().filter_map(|line| {
+        let fields = line.split_ascii_whitespace().collect::<Vec<&str>>();
+        match fields[..] {
+            [fs] => Some(fs.to_string()),
+            // zfs is marked nodev but still backed by disks
+            ["nodev", "zfs"] => Some("zfs".to_string()),
+            _ => None,
+        }
+    }).collect())
+}
+
+/// unescape decodes the octal escapes (`\040` for a space) the kernel uses
+/// for whitespace and backslashes in mount tables.
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).filter(|x| x.iter().all(|c| (b'0'..=b'7').contains(c)));
+        if let (b'\\', Some(digits)) = (bytes[i], octal) {
+            ret.push(digits.iter().fold(0u8, |acc, x| acc.wrapping_mul(8).wrapping_add(x - b'0')));
+            i += 4;
+        } else {
+            ret.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+/// normalize resolves the `..` components of a path without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                ret.pop();
+            }
+            Component::CurDir => {}
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+pub fn get_block_devices(ctx: &Context) -> Result<Vec<BlockDeviceStat>> {
+    let mut ret = Vec::new();
+
+    // /sys/block only lists whole devices, their partitions are subdirectories
+    for name in list_dir(&ctx.sys_path(SYS_BLOCK))? {
+        let dir = ctx.sys_path(SYS_BLOCK).join(&name);
+        let mut disk = block_device(&dir, &name)?;
+        disk.kind = if dir.join("dm").exists() {
+            BlockDeviceKind::DeviceMapper
+        } else if dir.join("md").exists() {
+            BlockDeviceKind::Raid
+        } else if name.starts_with("loop") {
+            BlockDeviceKind::Loop
+        } else {
+            BlockDeviceKind::Disk
+        };
+
+        // the queue is the disk's, the model and WWN those of its hardware
+        let device = |file: &str| -> Result<String> {
+            Ok(read_attr(&dir.join("device").join(file))?.unwrap_or_default())
+        };
+        disk.model = device("model")?;
+        disk.vendor = device("vendor")?;
+        disk.wwn = match read_attr(&dir.join("wwid"))? {
+            Some(wwn) => wwn,
+            None => device("wwid")?,
+        };
+        disk.queue_depth = parse_attr(&dir.join("device/queue_depth"))?;
+        disk.nr_requests = parse_attr(&dir.join("queue/nr_requests"))?;
+        disk.logical_sector_size = parse_attr(&dir.join("queue/logical_block_size"))?;
+        disk.physical_sector_size = parse_attr(&dir.join("queue/physical_block_size"))?;
+        disk.rotational = parse_attr::<u8>(&dir.join("queue/rotational"))? == 1;
+        disk.removable = parse_attr::<u8>(&dir.join("removable"))? == 1;
+        disk.scheduler = read_attr(&dir.join("queue/scheduler"))?.map(|x| scheduler(&x)).unwrap_or_default();
+
+        let mut partitions = Vec::new();
+        for child in list_dir(&dir)? {
+            let number = dir.join(&child).join("partition");
+            if number.exists() {
+                partitions.push((parse_attr::<u32>(&number)?, child));
+            }
+        }
+        partitions.sort();
+
+        disk.children = partitions.iter().map(|(_, x)| x.clone()).collect();
+        let mut children = Vec::new();
+        for (_, child) in partitions {
+            let partition = block_device(&dir.join(&child), &child)?;
+            children.push(BlockDeviceStat {
+                kind: BlockDeviceKind::Partition,
+                parent: Some(disk.name.clone()),
+                // partitions share the queue of their disk
+                logical_sector_size: disk.logical_sector_size,
+                physical_sector_size: disk.physical_sector_size,
+                rotational: disk.rotational,
+                removable: disk.removable,
+                scheduler: disk.scheduler.clone(),
+                queue_depth: disk.queue_depth,
+                nr_requests: disk.nr_requests,
+                ..partition
+            });
+        }
+        ret.push(disk);
+        ret.extend(children);
+    }
+
+    Ok(ret)
+}
+
+/// block_device reads the attributes every block device, disk or partition,
+/// has in its sysfs directory `dir`.
+fn block_device(dir: &Path, name: &str) -> Result<BlockDeviceStat> {
+    let path = dir.join("dev");
+    let dev = read_attr(&path)?.unwrap_or_default();
+    let (major, minor) = match dev.split_once(':') {
+        Some((major, minor)) => (parse_field(major, &path, 1, "major")?, parse_field(minor, &path, 1, "minor")?),
+        None => (0, 0),
+    };
+
+    Ok(BlockDeviceStat {
+        name: name.to_string(),
+        major,
+        minor,
+        // in 512-byte sectors, whatever the sector size of the device
+        size: parse_attr::<u64>(&dir.join("size"))? * SECTOR_SIZE,
+        read_only: parse_attr::<u8>(&dir.join("ro"))? == 1,
+        slaves: list_dir(&dir.join("slaves"))?,
+        holders: list_dir(&dir.join("holders"))?,
+        ..Default::default()
+    })
+}
+
+/// scheduler returns the I/O scheduler selected in a queue/scheduler
+/// attribute like "mq-deadline kyber [bfq] none".
+fn scheduler(value: &str) -> String {
+    value.split_ascii_whitespace()
+        .find_map(|x| x.strip_prefix('[')?.strip_suffix(']'))
+        .unwrap_or(value.trim())
+        .to_string()
+}
+
+/// read_attr reads a sysfs attribute, or returns None if it does not exist.
+fn read_attr(path: &Path) -> Result<Option<String>> {
+    match cfs::read_line(path) {
+        Ok(v) => Ok(Some(v)),
+        Err(Error::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// parse_attr parses a numeric sysfs attribute, 0 if it does not exist.
+fn parse_attr<T: std::str::FromStr + Default>(path: &Path) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    match read_attr(path)? {
+        Some(v) => parse_field(&v, path, 1, path.file_name().and_then(OsStr::to_str).unwrap_or_default()),
+        None => Ok(T::default()),
+    }
+}
+
+/// list_dir returns the sorted names of the entries of `dir`, none if it
+/// does not exist.
+fn list_dir(dir: &Path) -> Result<Vec<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::io(dir, e)),
+    };
+
+    let mut ret = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::io(dir, e))?;
+        ret.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    ret.sort();
+    Ok(ret)
+}
+
 fn get_fs_type(type_id: isize) -> String {
     match type_id {
         0xadf5 => "adfs",          // ADFS_SUPER_MAGIC      /* 0xADF5 local */
@@ -1,52 +1,217 @@
-use std::error::Error;
-
 #[cfg(target_os = "linux")]
 use linux::*;
+#[cfg(not(target_os = "linux"))]
+use unsupported::*;
+use std::time::Duration;
+
+use crate::context::Context;
+use crate::disk::rate::*;
+use crate::error::Result;
 
 #[cfg(target_os = "linux")]
 mod linux;
+#[cfg(not(target_os = "linux"))]
+mod unsupported;
+mod rate;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct UsageStat {
-    path: String,
-    fs_type: String,
-    total: u64,
-    free: u64,
-    used: u64,
-    used_percent: f64,
-    inodes_total: u64,
-    inodes_used: u64,
-    inodes_free: u64,
-    inodes_used_percent: f64,
-}
-
-#[derive(Default, Debug)]
+    pub path: String,
+    pub fs_type: String,
+    pub total: u64,
+    pub free: u64,
+    pub used: u64,
+    pub used_percent: f64,
+    pub inodes_total: u64,
+    pub inodes_used: u64,
+    pub inodes_free: u64,
+    pub inodes_used_percent: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct PartitionStat {
-    device: String,
-    mountpoint: String,
-    fstype: String,
-    opts: Vec<String>,
+    pub device: String,
+    pub mountpoint: String,
+    pub fstype: String,
+    // Per-mount options followed by the superblock options not already listed
+    pub opts: Vec<String>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct IOCountersStat {
-    read_count: u64,
-    merged_read_count: u64,
-    write_count: u64,
-    merged_write_count: u64,
-    read_bytes: u64,
-    write_bytes: u64,
-    read_time: u64,
-    write_time: u64,
-    iops_in_progress: u64,
-    io_time: u64,
-    weighted_io: u64,
-    name: String,
-    serial_number: String,
-    label: String,
-}
-
-
-pub fn usage(path: &str) -> Result<UsageStat, Box<dyn Error>> {
+    pub read_count: u64,
+    pub merged_read_count: u64,
+    pub write_count: u64,
+    pub merged_write_count: u64,
+    /// Bytes read, and likewise written and discarded. /proc/diskstats counts
+    /// them in 512-byte sectors whatever the device's hw_sector_size, so they
+    /// are converted with 512 and never scaled by the hardware sector size.
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    // Times are in milliseconds
+    pub read_time: u64,
+    pub write_time: u64,
+    pub iops_in_progress: u64,
+    pub io_time: u64,
+    pub weighted_io: u64,
+    // Discards, since Linux 4.18
+    pub discard_count: u64,
+    pub merged_discard_count: u64,
+    pub discard_bytes: u64,
+    pub discard_time: u64,
+    // Flush requests, since Linux 5.5
+    pub flush_count: u64,
+    pub flush_time: u64,
+    pub name: String,
+    pub serial_number: String,
+    pub label: String,
+}
+
+/// IORateStat holds the per-second rates of a device between two
+/// IOCountersStat snapshots, named after the columns of `iostat -x`.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct IORateStat {
+    pub name: String,
+    // r/s, w/s, d/s and f/s
+    pub read_per_sec: f64,
+    pub write_per_sec: f64,
+    pub discard_per_sec: f64,
+    pub flush_per_sec: f64,
+    // rrqm/s and wrqm/s
+    pub merged_read_per_sec: f64,
+    pub merged_write_per_sec: f64,
+    // rMB/s, wMB/s and dMB/s, in MiB
+    pub read_mb_per_sec: f64,
+    pub write_mb_per_sec: f64,
+    pub discard_mb_per_sec: f64,
+    // Average time in milliseconds requests took to be served, queueing included
+    pub read_await: f64,
+    pub write_await: f64,
+    pub discard_await: f64,
+    pub flush_await: f64,
+    // await, over reads, writes and discards
+    pub avg_await: f64,
+    // aqu-sz
+    pub avg_queue_size: f64,
+    // %util, the share of time the device was busy
+    pub util_percent: f64,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockDeviceKind {
+    #[default]
+    Disk,
+    Partition,
+    Loop,
+    // dm-*, e.g. LVM volumes and dm-crypt mappings
+    DeviceMapper,
+    // md*, software RAID
+    Raid,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct BlockDeviceStat {
+    pub name: String,
+    pub major: u32,
+    pub minor: u32,
+    pub kind: BlockDeviceKind,
+    // Size in bytes
+    pub size: u64,
+    pub logical_sector_size: u64,
+    pub physical_sector_size: u64,
+    pub rotational: bool,
+    pub removable: bool,
+    pub read_only: bool,
+    // Model, vendor and WWN are only known for disks
+    pub model: String,
+    pub vendor: String,
+    pub wwn: String,
+    // Selected I/O scheduler, e.g. "mq-deadline" or "none"
+    pub scheduler: String,
+    // Tagged command queue depth of the hardware, 0 if unknown, and the
+    // number of requests the block layer queues
+    pub queue_depth: u32,
+    pub nr_requests: u32,
+    // Disk of a partition, and partitions of a disk
+    pub parent: Option<String>,
+    pub children: Vec<String>,
+    // Devices a dm-* or md* device is built on, and those built on this one
+    pub slaves: Vec<String>,
+    pub holders: Vec<String>,
+}
+
+pub fn usage(path: &str) -> Result<UsageStat> {
     get_usage(path)
-}
\ No newline at end of file
+}
+
+/// io_counters returns the I/O statistics of every block device and
+/// partition, or only of those in `names` (e.g. "sda", "nvme0n1p1") if it is
+/// not empty.
+pub fn io_counters(names: &[&str]) -> Result<Vec<IOCountersStat>> {
+    io_counters_with_context(&Context::default(), names)
+}
+
+pub fn io_counters_with_context(ctx: &Context, names: &[&str]) -> Result<Vec<IOCountersStat>> {
+    get_io_counters(ctx, names)
+}
+
+/// io_rates returns the throughput, IOPS, latency and utilisation of every
+/// block device and partition, or only of those in `names` if it is not
+/// empty, over `interval`, as `iostat -x` reports them.
+pub fn io_rates(interval: Duration, names: &[&str]) -> Result<Vec<IORateStat>> {
+    io_rates_with_context(&Context::default(), interval, names)
+}
+
+pub fn io_rates_with_context(ctx: &Context, interval: Duration, names: &[&str]) -> Result<Vec<IORateStat>> {
+    let (t1, t2, elapsed) = sample(ctx, interval, names)?;
+    Ok(calculate_all_rates(&t1, &t2, elapsed))
+}
+
+/// io_rates_between is like io_rates, but works on two snapshots taken with
+/// io_counters `elapsed` apart. Devices missing from either snapshot are left
+/// out, and counters that wrapped around or were reset between them are
+/// accounted for.
+pub fn io_rates_between(t1: &[IOCountersStat], t2: &[IOCountersStat], elapsed: Duration) -> Vec<IORateStat> {
+    calculate_all_rates(t1, t2, elapsed)
+}
+
+/// serial_number returns the serial number of the block device `name`
+/// (e.g. "sda" or "/dev/sda"), or an empty string if it is unknown.
+pub fn serial_number(name: &str) -> Result<String> {
+    serial_number_with_context(&Context::default(), name)
+}
+
+pub fn serial_number_with_context(ctx: &Context, name: &str) -> Result<String> {
+    get_serial_number(ctx, name)
+}
+
+/// label returns the label of the filesystem on the block device `name`
+/// (e.g. "sda1" or "/dev/sda1"), or an empty string if it has none.
+pub fn label(name: &str) -> Result<String> {
+    label_with_context(&Context::default(), name)
+}
+
+pub fn label_with_context(ctx: &Context, name: &str) -> Result<String> {
+    get_label(ctx, name)
+}
+
+/// block_devices returns every disk, each followed by its partitions, with
+/// their geometry, queue settings and relations to the others, much like
+/// `lsblk`.
+pub fn block_devices() -> Result<Vec<BlockDeviceStat>> {
+    block_devices_with_context(&Context::default())
+}
+
+pub fn block_devices_with_context(ctx: &Context) -> Result<Vec<BlockDeviceStat>> {
+    get_block_devices(ctx)
+}
+
+/// partitions returns the mounted filesystems. Unless `all` is set, pseudo
+/// filesystems such as proc, sysfs or tmpfs are left out.
+pub fn partitions(all: bool) -> Result<Vec<PartitionStat>> {
+    partitions_with_context(&Context::default(), all)
+}
+
+pub fn partitions_with_context(ctx: &Context, all: bool) -> Result<Vec<PartitionStat>> {
+    get_partitions(ctx, all)
+}
@@ -0,0 +1,101 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::context::Context;
+use crate::disk::{io_counters_with_context, IOCountersStat, IORateStat};
+use crate::error::Result;
+
+const MB: f64 = 1024.0 * 1024.0;
+
+// /proc/diskstats prints the times as 32-bit values, the request and sector
+// counts as unsigned longs, whose width is the kernel's, and counts bytes in
+// 512-byte sectors
+const TIME_WRAP: u128 = 1 << 32;
+const SECTOR_SIZE: u64 = 512;
+
+/// sample returns two snapshots of the counters of the devices in `names`
+/// taken `interval` apart, and the time actually elapsed between them.
+pub fn sample(ctx: &Context, interval: Duration, names: &[&str])
+              -> Result<(Vec<IOCountersStat>, Vec<IOCountersStat>, Duration)> {
+    let t1 = io_counters_with_context(ctx, names)?;
+    let start = Instant::now();
+    thread::sleep(interval);
+    let t2 = io_counters_with_context(ctx, names)?;
+    Ok((t1, t2, start.elapsed()))
+}
+
+/// calculate_all_rates returns the rates of every device in `t2` that is
+/// also in `t1`, matching devices by name. Devices that appeared or
+/// disappeared between both snapshots are left out.
+pub fn calculate_all_rates(t1: &[IOCountersStat], t2: &[IOCountersStat], elapsed: Duration) -> Vec<IORateStat> {
+    t2.iter().filter_map(|b| {
+        let a = t1.iter().find(|a| a.name == b.name)?;
+        Some(calculate_rates(a, b, elapsed))
+    }).collect()
+}
+
+/// calculate_rates computes the metrics of iostat -x from the deltas
+/// between `t1` and `t2`.
+fn calculate_rates(t1: &IOCountersStat, t2: &IOCountersStat, elapsed: Duration) -> IORateStat {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return IORateStat { name: t2.name.clone(), ..Default::default() };
+    }
+
+    let d = |a: u64, b: u64| counter_delta(a, b) as f64;
+    let bytes = |a: u64, b: u64| counter_delta(a / SECTOR_SIZE, b / SECTOR_SIZE) as f64 * SECTOR_SIZE as f64;
+    let ms = |a: u64, b: u64| delta(a, b, TIME_WRAP) as f64;
+    let reads = d(t1.read_count, t2.read_count);
+    let writes = d(t1.write_count, t2.write_count);
+    let discards = d(t1.discard_count, t2.discard_count);
+    let flushes = d(t1.flush_count, t2.flush_count);
+    let read_time = ms(t1.read_time, t2.read_time);
+    let write_time = ms(t1.write_time, t2.write_time);
+    let discard_time = ms(t1.discard_time, t2.discard_time);
+
+    // average time per request in ms, 0 if there was none
+    let wait = |time: f64, count: f64| if count > 0.0 { time / count } else { 0.0 };
+
+    IORateStat {
+        name: t2.name.clone(),
+        read_per_sec: reads / secs,
+        write_per_sec: writes / secs,
+        discard_per_sec: discards / secs,
+        flush_per_sec: flushes / secs,
+        merged_read_per_sec: d(t1.merged_read_count, t2.merged_read_count) / secs,
+        merged_write_per_sec: d(t1.merged_write_count, t2.merged_write_count) / secs,
+        read_mb_per_sec: bytes(t1.read_bytes, t2.read_bytes) / MB / secs,
+        write_mb_per_sec: bytes(t1.write_bytes, t2.write_bytes) / MB / secs,
+        discard_mb_per_sec: bytes(t1.discard_bytes, t2.discard_bytes) / MB / secs,
+        read_await: wait(read_time, reads),
+        write_await: wait(write_time, writes),
+        discard_await: wait(discard_time, discards),
+        flush_await: wait(ms(t1.flush_time, t2.flush_time), flushes),
+        avg_await: wait(read_time + write_time + discard_time, reads + writes + discards),
+        avg_queue_size: ms(t1.weighted_io, t2.weighted_io) / 1000.0 / secs,
+        util_percent: (ms(t1.io_time, t2.io_time) / 1000.0 / secs * 100.0).clamp(0.0, 100.0),
+    }
+}
+
+/// counter_delta subtracts `a` from `b`, two readings of an unsigned long
+/// counter. Its width can't be told from userspace: a counter that still
+/// fits in 32 bits is taken to wrap there, a larger one at 64 bits.
+fn counter_delta(a: u64, b: u64) -> u64 {
+    let wrap = if a.max(b) <= u32::MAX as u64 { 1 << 32 } else { 1 << 64 };
+    delta(a, b, wrap)
+}
+
+/// delta subtracts `a` from `b`, two readings of a counter that wraps at
+/// `wrap`. A counter going backwards from the upper half of its range has
+/// wrapped; from lower, it was reset, e.g. because the device was removed
+/// and another one took its name, and counts from 0.
+fn delta(a: u64, b: u64, wrap: u128) -> u64 {
+    let (a, b) = (a as u128, b as u128);
+    if b >= a {
+        (b - a) as u64
+    } else if a >= wrap / 2 && a < wrap {
+        (wrap - a + b) as u64
+    } else {
+        b as u64
+    }
+}
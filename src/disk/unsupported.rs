@@ -0,0 +1,27 @@
+use crate::context::Context;
+use crate::disk::{BlockDeviceStat, IOCountersStat, PartitionStat, UsageStat};
+use crate::error::{Error, Result};
+
+pub fn get_usage(_path: &str) -> Result<UsageStat> {
+    Err(Error::Unsupported("disk::usage"))
+}
+
+pub fn get_partitions(_ctx: &Context, _all: bool) -> Result<Vec<PartitionStat>> {
+    Err(Error::Unsupported("disk::partitions"))
+}
+
+pub fn get_io_counters(_ctx: &Context, _names: &[&str]) -> Result<Vec<IOCountersStat>> {
+    Err(Error::Unsupported("disk::io_counters"))
+}
+
+pub fn get_serial_number(_ctx: &Context, _name: &str) -> Result<String> {
+    Err(Error::Unsupported("disk::serial_number"))
+}
+
+pub fn get_label(_ctx: &Context, _name: &str) -> Result<String> {
+    Err(Error::Unsupported("disk::label"))
+}
+
+pub fn get_block_devices(_ctx: &Context) -> Result<Vec<BlockDeviceStat>> {
+    Err(Error::Unsupported("disk::block_devices"))
+}
@@ -0,0 +1,76 @@
+use std::{fmt, io};
+use std::path::{Path, PathBuf};
+
+/// Error is returned by every collector in this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading `path` failed, e.g. because the file does not exist on this kernel.
+    Io { path: PathBuf, source: io::Error },
+
+    /// The calling process is not allowed to read `path`.
+    PermissionDenied { path: PathBuf },
+
+    /// `path` was read, but `field` on line `line` (1-based, 0 when the source
+    /// is not line oriented) did not have the expected format.
+    Parse { path: PathBuf, line: usize, field: String, reason: String },
+
+    /// A system call or OS API that is not backed by a file failed.
+    Os { call: &'static str, source: io::Error },
+
+    /// The requested statistic is not implemented on this platform.
+    Unsupported(&'static str),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// io wraps an I/O error raised while accessing `path`, turning
+    /// permission failures into `Error::PermissionDenied`.
+    pub(crate) fn io<P: AsRef<Path>>(path: P, source: io::Error) -> Error {
+        let path = path.as_ref().to_path_buf();
+        if source.kind() == io::ErrorKind::PermissionDenied {
+            Error::PermissionDenied { path }
+        } else {
+            Error::Io { path, source }
+        }
+    }
+
+    pub(crate) fn parse<P: AsRef<Path>, R: fmt::Display>(path: P, line: usize, field: &str, reason: R) -> Error {
+        Error::Parse {
+            path: path.as_ref().to_path_buf(),
+            line,
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::PermissionDenied { path } => write!(f, "{}: permission denied", path.display()),
+            Error::Parse { path, line, field, reason } => {
+                write!(f, "couldn't parse {} line {} field {:?}: {}", path.display(), line, field, reason)
+            }
+            Error::Os { call, source } => write!(f, "{}: {}", call, source),
+            Error::Unsupported(what) => write!(f, "{} is not supported on this platform", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } | Error::Os { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl From<windows::core::Error> for Error {
+    fn from(e: windows::core::Error) -> Error {
+        Error::Os { call: "COM", source: e.into() }
+    }
+}
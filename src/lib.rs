@@ -0,0 +1,14 @@
+mod common;
+mod context;
+mod error;
+
+pub mod cpu;
+pub mod disk;
+pub mod load;
+pub mod mem;
+pub mod pressure;
+
+#[cfg(unix)]
+pub use common::sys::clock_ticks;
+pub use context::Context;
+pub use error::{Error, Result};
@@ -0,0 +1,92 @@
+use std::io;
+use std::path::Path;
+
+use crate::common::fs as cfs;
+use crate::common::parse::parse_field;
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::load::{AvgStat, MiscStat};
+
+const PROC_LOADAVG: &str = "loadavg";
+const PROC_STAT: &str = "stat";
+
+pub fn get_avg(ctx: &Context) -> Result<AvgStat> {
+    let path = ctx.proc_path(PROC_LOADAVG);
+    match cfs::read_line(&path) {
+        Ok(line) => parse_loadavg(&line, &path).map(|(avg, _)| avg),
+        // our own /proc may be masked, the kernel computes the same numbers for
+        // getloadavg. Another root is another host's, whose load it doesn't know.
+        Err(e) if is_own_proc(ctx) && is_masked(&e) => getloadavg(),
+        Err(e) => Err(e),
+    }
+}
+
+/// is_own_proc tells whether `ctx` reads the /proc of the calling process.
+fn is_own_proc(ctx: &Context) -> bool {
+    ctx.proc_path("") == Context::new().proc_path("")
+}
+
+fn is_masked(e: &Error) -> bool {
+    match e {
+        Error::Io { source, .. } => source.kind() == io::ErrorKind::NotFound,
+        Error::PermissionDenied { .. } => true,
+        _ => false,
+    }
+}
+
+pub fn get_misc(ctx: &Context) -> Result<MiscStat> {
+    let path = ctx.proc_path(PROC_STAT);
+    let lines = cfs::read_lines(&path)?;
+    let mut ret = MiscStat::default();
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut fields = line.split_whitespace();
+        let (Some(key), Some(value)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        let field = match key {
+            "processes" => &mut ret.procs_created,
+            "procs_running" => &mut ret.procs_running,
+            "procs_blocked" => &mut ret.procs_blocked,
+            "ctxt" => &mut ret.ctxt,
+            _ => continue,
+        };
+        *field = parse_field(value, &path, i + 1, key)?;
+    }
+
+    let path = ctx.proc_path(PROC_LOADAVG);
+    let (_, procs_total) = parse_loadavg(&cfs::read_line(&path)?, &path)?;
+    ret.procs_total = procs_total;
+
+    Ok(ret)
+}
+
+/// parse_loadavg parses the single line of /proc/loadavg, e.g.
+/// `0.52 0.58 0.59 2/1043 123456`, into the averages and the number of
+/// existing scheduling entities.
+fn parse_loadavg(line: &str, path: &Path) -> Result<(AvgStat, u64)> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    if fields.len() < 4 {
+        return Err(Error::parse(path, 1, "loadavg", "too few fields"));
+    }
+
+    let avg = AvgStat {
+        load1: parse_field(fields[0], path, 1, "load1")?,
+        load5: parse_field(fields[1], path, 1, "load5")?,
+        load15: parse_field(fields[2], path, 1, "load15")?,
+    };
+
+    let total = fields[3].split_once('/').map(|(_, total)| total).unwrap_or("");
+    Ok((avg, parse_field(total, path, 1, "procs_total")?))
+}
+
+fn getloadavg() -> Result<AvgStat> {
+    let mut loads = [0f64; 3];
+    let ret = unsafe { libc::getloadavg(loads.as_mut_ptr(), 3) };
+    if ret != 3 {
+        return Err(Error::Os { call: "getloadavg", source: io::Error::last_os_error() });
+    }
+
+    Ok(AvgStat { load1: loads[0], load5: loads[1], load15: loads[2] })
+}
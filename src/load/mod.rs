@@ -0,0 +1,48 @@
+#[cfg(target_os = "linux")]
+use crate::load::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::load::unsupported::*;
+use crate::context::Context;
+use crate::error::Result;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(not(target_os = "linux"))]
+mod unsupported;
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct AvgStat {
+    pub load1: f64,
+    pub load5: f64,
+    pub load15: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct MiscStat {
+    // Number of currently existing processes and threads
+    pub procs_total: u64,
+    // Number of processes and threads created since boot
+    pub procs_created: u64,
+    pub procs_running: u64,
+    pub procs_blocked: u64,
+    // Number of context switches since boot
+    pub ctxt: u64,
+}
+
+/// avg returns the 1, 5 and 15 minute load averages.
+pub fn avg() -> Result<AvgStat> {
+    avg_with_context(&Context::default())
+}
+
+pub fn avg_with_context(ctx: &Context) -> Result<AvgStat> {
+    get_avg(ctx)
+}
+
+/// misc returns the process and context switch counters of the run queue.
+pub fn misc() -> Result<MiscStat> {
+    misc_with_context(&Context::default())
+}
+
+pub fn misc_with_context(ctx: &Context) -> Result<MiscStat> {
+    get_misc(ctx)
+}
@@ -0,0 +1,11 @@
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::load::{AvgStat, MiscStat};
+
+pub fn get_avg(_ctx: &Context) -> Result<AvgStat> {
+    Err(Error::Unsupported("load::avg"))
+}
+
+pub fn get_misc(_ctx: &Context) -> Result<MiscStat> {
+    Err(Error::Unsupported("load::misc"))
+}
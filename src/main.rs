@@ -1,8 +1,18 @@
-mod common;
-mod cpu;
-mod mem;
-mod disk;
+use rspsutil::{cpu, disk, mem};
 
 fn main() {
-    println!("Hello, rspsutil!");
+    match cpu::counts(true) {
+        Ok(v) => println!("logical cpus: {}", v),
+        Err(e) => eprintln!("cpu counts: {}", e),
+    }
+
+    match mem::virtual_memory() {
+        Ok(v) => println!("memory: {} used of {} ({:.1}%)", v.used, v.total, v.used_percent),
+        Err(e) => eprintln!("virtual memory: {}", e),
+    }
+
+    match disk::usage("/") {
+        Ok(v) => println!("disk /: {} used of {} ({:.1}%)", v.used, v.total, v.used_percent),
+        Err(e) => eprintln!("disk usage: {}", e),
+    }
 }
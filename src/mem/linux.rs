@@ -1,16 +1,16 @@
 use std::cmp::min;
 use std::collections::HashMap;
-use std::error::Error;
-use std::io;
-use std::mem::MaybeUninit;
 
 use crate::common::fs as cfs;
+use crate::common::parse::parse_field;
+use crate::context::Context;
+use crate::error::{Error, Result};
 use crate::mem::{SwapDevice, SwapMemoryStat, VirtualMemoryStat};
 
-const PROC_MEMINFO: &str = "/proc/meminfo";
-const PROC_ZONEINFO: &str = "/proc/zoneinfo";
-const PROC_VMSTAT: &str = "/proc/vmstat";
-const PROC_SWAPS: &str = "/proc/swaps";
+const PROC_MEMINFO: &str = "meminfo";
+const PROC_ZONEINFO: &str = "zoneinfo";
+const PROC_VMSTAT: &str = "vmstat";
+const PROC_SWAPS: &str = "swaps";
 
 
 #[derive(Default, Debug)]
@@ -22,8 +22,8 @@ struct ExVirtualMemory {
     unevictable: u64,
 }
 
-pub fn get_virtual_memory() -> Result<VirtualMemoryStat, Box<dyn Error>> {
-    let lines = cfs::read_lines(PROC_MEMINFO)?;
+pub fn get_virtual_memory(ctx: &Context) -> Result<VirtualMemoryStat> {
+    let lines = cfs::read_lines(ctx.proc_path(PROC_MEMINFO))?;
     let mut ret = VirtualMemoryStat { ..Default::default() };
     let mut ret_ex = ExVirtualMemory { ..Default::default() };
 
@@ -34,11 +34,15 @@ pub fn get_virtual_memory() -> Result<VirtualMemoryStat, Box<dyn Error>> {
         }
 
         let key = fields[0].trim();
-        let value = fields[1].trim().replace(" kB", "");
+        let value = fields[1].trim();
 
-        if let Ok(mut v) = value.parse::<u64>() {
-            // v = v * 1024
-            v = v << 10;
+        // HugePages_* are page counts, everything else is reported in kB
+        let v = match value.strip_suffix(" kB") {
+            Some(kb) => kb.parse::<u64>().map(|v| v << 10),
+            None => value.parse::<u64>(),
+        };
+
+        if let Ok(v) = v {
 
             match key {
                 "MemTotal" => ret.total = v,
@@ -86,28 +90,33 @@ pub fn get_virtual_memory() -> Result<VirtualMemoryStat, Box<dyn Error>> {
     }
 
     ret.cached += ret.sreclaimable;
-    if ret.available > 0 {
+    if ret.available == 0 {
+        // MemAvailable is only exported since Linux 3.14
         if ret_ex.active_file > 0 && ret_ex.inactive_file > 0 && ret.sreclaimable > 0 {
-            ret.available = calculate_avail_vmem(&ret, &ret_ex);
+            ret.available = calculate_avail_vmem(ctx, &ret, &ret_ex);
         } else {
             ret.available = ret.cached + ret.free;
         }
     }
 
-    ret.used = ret.total - ret.free - ret.buffers - ret.cached;
-    ret.used_percent = ret.used as f64 / ret.total as f64 * 100.0;
+    ret.used = ret.total.saturating_sub(ret.free + ret.buffers + ret.cached);
+    if ret.total != 0 {
+        ret.used_percent = ret.used as f64 / ret.total as f64 * 100.0;
+    }
 
     Ok(ret)
 }
 
-pub fn get_swap_memory() -> Result<SwapMemoryStat, Box<dyn Error>> {
-    let info = sys_info()?;
+pub fn get_swap_memory(ctx: &Context) -> Result<SwapMemoryStat> {
+    // the SwapTotal and SwapFree of /proc/meminfo are those of sysinfo(2),
+    // but come from the context's root
+    let mem = get_virtual_memory(ctx)?;
     let mut ret = SwapMemoryStat {
-        total: info.totalswap * info.mem_unit as u64,
-        free: info.freeswap * info.mem_unit as u64,
+        total: mem.swap_total,
+        free: mem.swap_free,
         ..Default::default()
     };
-    ret.used = ret.total - ret.free;
+    ret.used = ret.total.saturating_sub(ret.free);
 
     // check Infinity
     if ret.total != 0 {
@@ -117,7 +126,7 @@ pub fn get_swap_memory() -> Result<SwapMemoryStat, Box<dyn Error>> {
     }
 
 
-    let lines = cfs::read_lines(PROC_VMSTAT)?;
+    let lines = cfs::read_lines(ctx.proc_path(PROC_VMSTAT))?;
     let kv = lines.iter().filter_map(|line| {
         let mut parts = line.split_whitespace();
         let key = parts.next()?;
@@ -130,59 +139,55 @@ pub fn get_swap_memory() -> Result<SwapMemoryStat, Box<dyn Error>> {
     ret.sout = get_value("pswpout");
     ret.pg_in = get_value("pgpgin");
     ret.pg_out = get_value("pgpgout");
-    ret.pg_fault = get_value("pgpgfault");
+    ret.pg_fault = get_value("pgfault");
     ret.pg_maj_fault = get_value("pgmajfault");
     Ok(ret)
 }
 
-pub fn get_swap_devices() -> Result<Vec<SwapDevice>, Box<dyn Error>> {
-    let lines = cfs::read_lines(PROC_SWAPS)?;
+pub fn get_swap_devices(ctx: &Context) -> Result<Vec<SwapDevice>> {
+    let path = ctx.proc_path(PROC_SWAPS);
+    let lines = cfs::read_lines(&path)?;
 
     if lines.len() < 2 {
         return Ok(vec![]);
     }
 
-    let headers = lines[0].split_ascii_whitespace().
-        into_iter().map(String::from).collect::<Vec<String>>();
+    let headers = lines[0].split_ascii_whitespace()
+        .map(String::from).collect::<Vec<String>>();
 
-    if headers.len() < 3 {
-        return Err(Box::new(io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("couldn't parse {PROC_SWAPS}: too few fields in header"))));
+    if headers.len() < 4 {
+        return Err(Error::parse(&path, 1, "header", "too few fields in header"));
     }
 
     if headers[0] != "Filename" || headers[2] != "Size" || headers[3] != "Used" {
-        return Err(Box::new(io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("couldn't parse {PROC_SWAPS}: headers unexpected fields"))));
+        return Err(Error::parse(&path, 1, "header", "headers unexpected fields"));
     }
 
     let mut ret = vec![];
-    for line in lines[1..].iter() {
-        let fields = line.split_ascii_whitespace().
-            into_iter().map(String::from).collect::<Vec<String>>();
-        if fields.len() < 3 {
-            return Err(Box::new(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("couldn't parse {PROC_SWAPS}: too few fields in header"))));
+    for (i, line) in lines.iter().enumerate().skip(1) {
+        let fields = line.split_ascii_whitespace()
+            .map(String::from).collect::<Vec<String>>();
+        if fields.len() < 4 {
+            return Err(Error::parse(&path, i + 1, "Filename", "too few fields"));
         }
 
 
-        let total_kb = fields[2].parse::<u64>()?;
-        let used_kb = fields[3].parse::<u64>()?;
+        let total_kb = parse_field::<u64, _>(&fields[2], &path, i + 1, "Size")?;
+        let used_kb = parse_field::<u64, _>(&fields[3], &path, i + 1, "Used")?;
 
         ret.push(SwapDevice {
             name: fields[0].clone(),
             used_bytes: used_kb << 10,
-            free_bytes: (total_kb - used_kb) << 10,
+            // Used briefly exceeds Size while the device is swapped off
+            free_bytes: total_kb.saturating_sub(used_kb) << 10,
         })
     }
 
     Ok(ret)
 }
 
-fn calculate_avail_vmem(ret: &VirtualMemoryStat, ret_ex: &ExVirtualMemory) -> u64 {
-    if let Ok(lines) = cfs::read_lines(PROC_ZONEINFO) {
+fn calculate_avail_vmem(ctx: &Context, ret: &VirtualMemoryStat, ret_ex: &ExVirtualMemory) -> u64 {
+    if let Ok(lines) = cfs::read_lines(ctx.proc_path(PROC_ZONEINFO)) {
         let mut watermark_low = 0;
 
         for line in lines {
@@ -192,14 +197,15 @@ fn calculate_avail_vmem(ret: &VirtualMemoryStat, ret_ex: &ExVirtualMemory) -> u6
             }
         }
 
-        watermark_low += page_size() as u64;
-        let mut avali_memory = ret.free - watermark_low;
+        // zoneinfo watermarks are counted in pages
+        watermark_low *= page_size() as u64;
+        let mut avali_memory = ret.free.saturating_sub(watermark_low);
         let mut page_cache = ret_ex.active_file + ret_ex.inactive_file;
         page_cache -= min(page_cache / 2, watermark_low);
         avali_memory += page_cache;
         avali_memory += ret.sreclaimable - min(ret.sreclaimable / 2, watermark_low);
 
-        return if avali_memory < 0 { 0 } else { avali_memory };
+        return avali_memory;
     }
 
     ret.free + ret.cached
@@ -207,16 +213,4 @@ fn calculate_avail_vmem(ret: &VirtualMemoryStat, ret_ex: &ExVirtualMemory) -> u6
 
 fn page_size() -> usize {
     unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
-}
-
-fn sys_info() -> Result<libc::sysinfo, Box<dyn Error>> {
-    let mut info = MaybeUninit::uninit();
-    let ret = unsafe { libc::sysinfo(info.as_mut_ptr()) };
-
-    let info = unsafe { info.assume_init() };
-    if ret == 0 {
-        Ok(info)
-    } else {
-        Err(io::Error::last_os_error().into())
-    }
 }
\ No newline at end of file
@@ -1,12 +1,16 @@
-use std::error::Error;
-
 #[cfg(target_os = "linux")]
 use crate::mem::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::mem::unsupported::*;
+use crate::context::Context;
+use crate::error::Result;
 
 #[cfg(target_os = "linux")]
 mod linux;
+#[cfg(not(target_os = "linux"))]
+mod unsupported;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct VirtualMemoryStat {
     // Total amount of RAM on this system
     pub total: u64,
@@ -77,7 +81,7 @@ pub struct VirtualMemoryStat {
     pub anon_huge_pages: u64,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct SwapMemoryStat {
     pub total: u64,
     pub used: u64,
@@ -93,21 +97,33 @@ pub struct SwapMemoryStat {
     pub pg_maj_fault: u64,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct SwapDevice {
-    name: String,
-    used_bytes: u64,
-    free_bytes: u64,
+    pub name: String,
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+}
+
+pub fn virtual_memory() -> Result<VirtualMemoryStat> {
+    virtual_memory_with_context(&Context::default())
+}
+
+pub fn virtual_memory_with_context(ctx: &Context) -> Result<VirtualMemoryStat> {
+    get_virtual_memory(ctx)
+}
+
+pub fn swap_memory() -> Result<SwapMemoryStat> {
+    swap_memory_with_context(&Context::default())
 }
 
-pub fn virtual_memory() -> Result<VirtualMemoryStat, Box<dyn Error>> {
-    get_virtual_memory()
+pub fn swap_memory_with_context(ctx: &Context) -> Result<SwapMemoryStat> {
+    get_swap_memory(ctx)
 }
 
-pub fn swap_memory() -> Result<SwapMemoryStat, Box<dyn Error>> {
-    get_swap_memory()
+pub fn swap_devices() -> Result<Vec<SwapDevice>> {
+    swap_devices_with_context(&Context::default())
 }
 
-pub fn swap_devices() -> Result<Vec<SwapDevice>, Box<dyn Error>> {
-    get_swap_devices()
-}
\ No newline at end of file
+pub fn swap_devices_with_context(ctx: &Context) -> Result<Vec<SwapDevice>> {
+    get_swap_devices(ctx)
+}
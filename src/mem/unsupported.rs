@@ -0,0 +1,15 @@
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::mem::{SwapDevice, SwapMemoryStat, VirtualMemoryStat};
+
+pub fn get_virtual_memory(_ctx: &Context) -> Result<VirtualMemoryStat> {
+    Err(Error::Unsupported("mem::virtual_memory"))
+}
+
+pub fn get_swap_memory(_ctx: &Context) -> Result<SwapMemoryStat> {
+    Err(Error::Unsupported("mem::swap_memory"))
+}
+
+pub fn get_swap_devices(_ctx: &Context) -> Result<Vec<SwapDevice>> {
+    Err(Error::Unsupported("mem::swap_devices"))
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use crate::common::fs as cfs;
+use crate::common::parse::parse_field;
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::pressure::{PressureStat, Resource, StallStat};
+
+const PROC_PRESSURE: &str = "pressure";
+
+pub fn system_pressure(ctx: &Context, resource: Resource) -> Result<PressureStat> {
+    read_pressure(&ctx.proc_path(PROC_PRESSURE).join(resource.name()))
+}
+
+pub fn cgroup_pressure(dir: &Path, resource: Resource) -> Result<PressureStat> {
+    read_pressure(&dir.join(format!("{}.pressure", resource.name())))
+}
+
+/// read_pressure parses a PSI file such as
+///
+/// ```text
+/// some avg10=0.00 avg60=0.12 avg300=0.08 total=1426381
+/// full avg10=0.00 avg60=0.05 avg300=0.03 total=725433
+/// ```
+fn read_pressure(path: &Path) -> Result<PressureStat> {
+    let lines = cfs::read_lines(path)?;
+    let mut ret = PressureStat::default();
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut fields = line.split_ascii_whitespace();
+        let kind = match fields.next() {
+            Some("some") => &mut ret.some,
+            Some("full") => &mut ret.full,
+            _ => continue,
+        };
+
+        let mut stat = StallStat::default();
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else {
+                return Err(Error::parse(path, i + 1, field, "expected key=value"));
+            };
+            match key {
+                "avg10" => stat.avg10 = parse_field(value, path, i + 1, key)?,
+                "avg60" => stat.avg60 = parse_field(value, path, i + 1, key)?,
+                "avg300" => stat.avg300 = parse_field(value, path, i + 1, key)?,
+                "total" => stat.total = parse_field(value, path, i + 1, key)?,
+                _ => {}
+            }
+        }
+        *kind = Some(stat);
+    }
+
+    Ok(ret)
+}
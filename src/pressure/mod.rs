@@ -0,0 +1,72 @@
+#[cfg(target_os = "linux")]
+use crate::pressure::linux::*;
+#[cfg(not(target_os = "linux"))]
+use crate::pressure::unsupported::*;
+use std::path::Path;
+
+use crate::context::Context;
+use crate::error::Result;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(not(target_os = "linux"))]
+mod unsupported;
+
+/// Resource selects which Pressure Stall Information file to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+    // Time spent servicing interrupts, since Linux 6.1
+    Irq,
+}
+
+impl Resource {
+    /// name returns the file name of the resource, e.g. "memory".
+    pub fn name(self) -> &'static str {
+        match self {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+            Resource::Io => "io",
+            Resource::Irq => "irq",
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct PressureStat {
+    // Share of time at least some tasks were stalled on the resource.
+    // Not reported for irq.
+    pub some: Option<StallStat>,
+    // Share of time all non-idle tasks were stalled at once. Not reported
+    // for cpu before Linux 5.13, and always 0 for cpu outside of cgroups.
+    pub full: Option<StallStat>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct StallStat {
+    // Percentage of time stalled over the last 10, 60 and 300 seconds
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    // Total stall time in microseconds
+    pub total: u64,
+}
+
+/// stat returns the system-wide pressure stall information of a resource
+/// from /proc/pressure.
+pub fn stat(resource: Resource) -> Result<PressureStat> {
+    stat_with_context(&Context::default(), resource)
+}
+
+pub fn stat_with_context(ctx: &Context, resource: Resource) -> Result<PressureStat> {
+    system_pressure(ctx, resource)
+}
+
+/// cgroup_stat returns the pressure stall information of a resource for the
+/// tasks of a cgroup v2 directory, e.g. `/sys/fs/cgroup/system.slice`, from
+/// its `<resource>.pressure` file.
+pub fn cgroup_stat<P: AsRef<Path>>(dir: P, resource: Resource) -> Result<PressureStat> {
+    cgroup_pressure(dir.as_ref(), resource)
+}
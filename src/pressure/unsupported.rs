@@ -0,0 +1,13 @@
+use std::path::Path;
+
+use crate::context::Context;
+use crate::error::{Error, Result};
+use crate::pressure::{PressureStat, Resource};
+
+pub fn system_pressure(_ctx: &Context, _resource: Resource) -> Result<PressureStat> {
+    Err(Error::Unsupported("pressure::stat"))
+}
+
+pub fn cgroup_pressure(_dir: &Path, _resource: Resource) -> Result<PressureStat> {
+    Err(Error::Unsupported("pressure::cgroup_stat"))
+}
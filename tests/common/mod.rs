@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use rspsutil::Context;
+
+/// fixture returns a context rooted at `tests/fixtures/<name>`, a captured
+/// `/proc` and `/sys` tree from the named machine.
+pub fn fixture(name: &str) -> Context {
+    Context::from_root(fixture_dir(name))
+}
+
+pub fn fixture_dir(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// ticks converts a raw /proc/stat value into the seconds reported by `TimesStat`.
+pub fn ticks(v: u64) -> f64 {
+    v as f64 / rspsutil::clock_ticks() as f64
+}
+
+pub fn strings(v: &str) -> Vec<String> {
+    v.split(' ').map(String::from).collect()
+}
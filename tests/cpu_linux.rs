@@ -0,0 +1,693 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{fixture, strings, ticks};
+use rspsutil::cpu::{
+    self, CacheStat, CoreStat, CoreType, CpuFlag, CpuFlags, CpuSet, CpuSetKind, DieStat, EffectiveCountsStat, FreqStat,
+    InfoStat, InterruptStat, NumaNodeStat, PackageStat, SchedDomainStat, SchedStat, SoftInterruptStat, StatsStat,
+    TimesStat, TopologyStat, VulnerabilityStat, VulnerabilityStatus,
+};
+
+const X86_FLAGS: &str = "fpu vme de pse tsc msr pae mce cx8 apic sep mtrr pge mca cmov pat pse36 clflush mmx fxsr sse sse2 ht syscall nx pdpe1gb rdtscp lm constant_tsc rep_good nopl xtopology nonstop_tsc cpuid pni pclmulqdq ssse3 fma cx16 pcid sse4_1 sse4_2 x2apic movbe popcnt aes xsave avx f16c rdrand hypervisor lahf_lm abm 3dnowprefetch invpcid_single fsgsbase bmi1 avx2 smep bmi2 erms invpcid avx512f avx512dq rdseed adx smap clflushopt clwb avx512cd avx512bw avx512vl xsaveopt xsavec xgetbv1 xsaves arat pku ospke";
+const ARM_FEATURES: &str = "fp asimd evtstrm aes pmull sha1 sha2 crc32 atomics fphp asimdhp cpuid asimdrdm lrcpc dcpop asimddp ssbs";
+
+fn set(cpus: &str) -> CpuSet {
+    cpus.parse().unwrap()
+}
+
+fn times(cpu: &str, v: [u64; 10]) -> TimesStat {
+    TimesStat {
+        cpu: cpu.to_string(),
+        user: ticks(v[0]),
+        nice: ticks(v[1]),
+        system: ticks(v[2]),
+        idle: ticks(v[3]),
+        io_wait: ticks(v[4]),
+        irq: ticks(v[5]),
+        soft_irq: ticks(v[6]),
+        steal: ticks(v[7]),
+        guest: ticks(v[8]),
+        guest_nice: ticks(v[9]),
+    }
+}
+
+#[test]
+fn total_times_reads_only_the_aggregate_line() {
+    let ctx = fixture("x86_64");
+    assert_eq!(
+        cpu::times_with_context(&ctx, false).unwrap(),
+        vec![times("cpu-total", [1093510, 4127, 301892, 37458101, 12056, 0, 8213, 0, 0, 0])],
+    );
+}
+
+#[test]
+fn per_cpu_times_stop_at_first_non_cpu_line() {
+    let ctx = fixture("x86_64");
+    assert_eq!(
+        cpu::times_with_context(&ctx, true).unwrap(),
+        vec![
+            times("cpu0", [272840, 1032, 76123, 9360121, 3104, 0, 4380, 0, 0, 0]),
+            times("cpu1", [274105, 998, 75040, 9366502, 2977, 0, 1290, 0, 0, 0]),
+            times("cpu2", [273011, 1043, 75602, 9365020, 3011, 0, 1302, 0, 0, 0]),
+            times("cpu3", [273554, 1054, 75127, 9366458, 2964, 0, 1241, 0, 0, 0]),
+        ],
+    );
+}
+
+#[test]
+fn times_with_steal() {
+    assert_eq!(
+        cpu::times_with_context(&fixture("s390x"), true).unwrap(),
+        vec![
+            times("cpu0", [7301, 6, 3512, 1415610, 355, 0, 51, 201, 0, 0]),
+            times("cpu1", [7220, 6, 3418, 1416130, 356, 0, 51, 190, 0, 0]),
+        ],
+    );
+}
+
+#[test]
+fn times_kvm() {
+    assert_eq!(
+        cpu::times_with_context(&fixture("kvm"), true).unwrap(),
+        vec![times("cpu0", [41844, 0, 10330, 359425, 556, 0, 10, 739, 0, 0])],
+    );
+}
+
+#[test]
+fn times_missing_stat_is_io_error() {
+    let ctx = fixture("does-not-exist");
+    assert!(matches!(cpu::times_with_context(&ctx, false), Err(rspsutil::Error::Io { .. })));
+}
+
+#[test]
+fn infos_x86_64() {
+    let ctx = fixture("x86_64");
+    let expected = (0..4).map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "GenuineIntel".to_string(),
+        family: "6".to_string(),
+        model: "85".to_string(),
+        stepping: 7,
+        physical_id: "0".to_string(),
+        core_id: (cpu % 2).to_string(),
+        cores: 1,
+        model_name: "Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz".to_string(),
+        // cpufreq/cpuinfo_max_freq overrides the "cpu MHz" line
+        mhz: 3500.0,
+        cache_size: 36608,
+        flags: X86_FLAGS.parse().unwrap(),
+        microcode: "0x5003604".to_string(),
+        core_type: CoreType::Unknown,
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+}
+
+#[test]
+fn infos_kvm() {
+    let infos = cpu::infos_with_context(&fixture("kvm")).unwrap();
+    let flags = infos[0].flags.clone();
+    assert_eq!(infos, vec![InfoStat {
+        cpu: 0,
+        vendor_id: "GenuineIntel".to_string(),
+        family: "6".to_string(),
+        model: "143".to_string(),
+        stepping: 8,
+        physical_id: "0".to_string(),
+        core_id: "0".to_string(),
+        cores: 1,
+        model_name: "Intel(R) Xeon(R) Processor".to_string(),
+        // no cpufreq in the guest
+        mhz: 2000.0,
+        cache_size: 107520,
+        flags,
+        microcode: "0x1".to_string(),
+        core_type: CoreType::Unknown,
+    }]);
+    assert_eq!(infos[0].flags.len(), 117);
+    assert!(infos[0].flags.contains(CpuFlag::Avx512f));
+    assert!(infos[0].flags.contains_name("amx_tile"));
+    assert!(infos[0].flags.contains_name("hypervisor"));
+}
+
+#[test]
+fn infos_aarch64() {
+    let ctx = fixture("aarch64");
+    // cpu1 is offline
+    let expected = [0, 2].into_iter().map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "ARM".to_string(),
+        model: "0xd0c".to_string(),
+        stepping: 1,
+        core_id: cpu.to_string(),
+        cores: 1,
+        model_name: "Neoverse-N1".to_string(),
+        flags: ARM_FEATURES.parse().unwrap(),
+        ..Default::default()
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+}
+
+#[test]
+fn infos_aarch64_vendors() {
+    let infos = cpu::infos_with_context(&fixture("aarch64_vendors")).unwrap();
+    let names = infos.iter().map(|x| (x.vendor_id.as_str(), x.model.as_str(), x.model_name.as_str())).collect::<Vec<_>>();
+    assert_eq!(names, vec![
+        ("Cavium", "0x0af", "ThunderX2-99xx"),
+        ("Fujitsu", "0x001", "A64FX"),
+        ("HiSilicon", "0xd01", "TaiShan-v110"),
+        ("Apple", "0x022", "Icestorm-M1"),
+        ("Apple", "0x023", "Firestorm-M1"),
+        ("Qualcomm", "0x001", "Oryon"),
+        ("Ampere", "0xac3", "Ampere-1"),
+        ("NVIDIA", "0x004", "Carmel"),
+        ("ARM", "0xd4f", "Neoverse-V2"),
+        ("ARM", "0xd0c", "Neoverse-N1"),
+        ("", "0x123", "Undefined"),
+    ]);
+}
+
+#[test]
+fn infos_power() {
+    let ctx = fixture("power");
+    let expected = (0..2).map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "IBM".to_string(),
+        family: "POWER".to_string(),
+        model: "POWER9".to_string(),
+        stepping: 2,
+        cores: 1,
+        model_name: "POWER9 (architected), altivec supported".to_string(),
+        mhz: 2200.0,
+        ..Default::default()
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+}
+
+#[test]
+fn infos_s390x() {
+    let ctx = fixture("s390x");
+    let expected = (0..2).map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "IBM/S390".to_string(),
+        physical_id: "0".to_string(),
+        core_id: cpu.to_string(),
+        cores: 1,
+        model_name: "S390".to_string(),
+        mhz: 5200.0,
+        ..Default::default()
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+}
+
+#[test]
+fn infos_riscv64() {
+    let ctx = fixture("riscv64");
+    let expected = (0..2).map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "T-Head".to_string(),
+        model: "0x0".to_string(),
+        core_id: cpu.to_string(),
+        cores: 1,
+        model_name: "thead,c910".to_string(),
+        flags: "i m a f d c zicntr zicsr zifencei zihpm xtheadvector".parse().unwrap(),
+        microcode: "0x0".to_string(),
+        ..Default::default()
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+    assert!(expected[0].flags.contains(CpuFlag::C));
+    assert_eq!(expected[0].flags.unknown().collect::<Vec<_>>(), vec!["xtheadvector"]);
+
+    // older kernels print the riscv,isa of the devicetree, versions included
+    let infos = cpu::infos_with_context(&fixture("riscv64_dt")).unwrap();
+    assert_eq!(infos.len(), 2);
+    assert_eq!(infos[1].flags, "i m a f d c zicsr zifencei zba zbb".parse().unwrap());
+    assert_eq!((infos[1].vendor_id.as_str(), infos[1].model_name.as_str()), ("", "sifive,u74-mc"));
+}
+
+#[test]
+fn infos_loongarch64() {
+    let ctx = fixture("loongarch64");
+    let expected = (0..2).map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "Loongson".to_string(),
+        family: "Loongson-64bit".to_string(),
+        stepping: 0x10,
+        physical_id: "0".to_string(),
+        core_id: cpu.to_string(),
+        cores: 1,
+        model_name: "Loongson-3A5000".to_string(),
+        mhz: 2500.0,
+        flags: "cpucfg lam ual fpu lsx lasx crc32 complex crypto lvz lbt_x86 lbt_arm lbt_mips".parse().unwrap(),
+        ..Default::default()
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+}
+
+#[test]
+fn infos_container_without_sysfs() {
+    let ctx = fixture("container");
+    let expected = (0..2).map(|cpu| InfoStat {
+        cpu,
+        vendor_id: "AuthenticAMD".to_string(),
+        family: "25".to_string(),
+        model: "1".to_string(),
+        stepping: 1,
+        physical_id: "0".to_string(),
+        core_id: "0".to_string(),
+        cores: 1,
+        model_name: "AMD EPYC 7R13 Processor".to_string(),
+        mhz: 2650.0,
+        cache_size: 512,
+        flags: "fpu vme de pse tsc msr pae mce cx8 apic sep sse sse2 ht syscall nx lm avx avx2 sha_ni".parse().unwrap(),
+        microcode: "0xa0011d1".to_string(),
+        core_type: CoreType::Unknown,
+    }).collect::<Vec<_>>();
+
+    assert_eq!(cpu::infos_with_context(&ctx).unwrap(), expected);
+}
+
+#[test]
+fn counts() {
+    for (name, logical, physical) in [
+        ("x86_64", 4, 2),
+        ("aarch64", 2, 2),
+        ("power", 2, 0),
+        ("s390x", 2, 2),
+        ("container", 2, 1),
+    ] {
+        let ctx = fixture(name);
+        assert_eq!(cpu::counts_with_context(&ctx, true).unwrap(), logical, "{} logical", name);
+        assert_eq!(cpu::counts_with_context(&ctx, false).unwrap(), physical, "{} physical", name);
+    }
+}
+
+fn freq(cpu: &str, current: f64, min: f64, max: f64, governor: &str, available: &str) -> FreqStat {
+    FreqStat {
+        cpu: cpu.to_string(),
+        current,
+        min,
+        max,
+        governor: governor.to_string(),
+        available_governors: if available.is_empty() { vec![] } else { strings(available) },
+    }
+}
+
+#[test]
+fn freq_from_cpufreq() {
+    let ctx = fixture("x86_64");
+    assert_eq!(cpu::freq_with_context(&ctx, true).unwrap(), vec![
+        freq("cpu0", 2494.14, 1200.0, 3500.0, "powersave", "performance powersave"),
+        freq("cpu1", 3100.0, 1200.0, 3500.0, "powersave", "performance powersave"),
+        freq("cpu2", 1200.0, 1200.0, 3500.0, "powersave", "performance powersave"),
+        freq("cpu3", 2500.0, 1200.0, 3500.0, "powersave", "performance powersave"),
+    ]);
+    assert_eq!(cpu::freq_with_context(&ctx, false).unwrap(), vec![
+        freq("cpu-total", (2494.14 + 3100.0 + 1200.0 + 2500.0) / 4.0, 1200.0, 3500.0, "powersave", "performance powersave"),
+    ]);
+}
+
+#[test]
+fn freq_falls_back_to_cpuinfo() {
+    assert_eq!(cpu::freq_with_context(&fixture("container"), true).unwrap(), vec![
+        freq("cpu0", 2650.0, 0.0, 0.0, "", ""),
+        freq("cpu1", 2650.0, 0.0, 0.0, "", ""),
+    ]);
+    // cpu1 is offline, and missing from /proc/cpuinfo
+    assert_eq!(cpu::freq_with_context(&fixture("offline"), true).unwrap(), vec![
+        freq("cpu0", 2650.0, 0.0, 0.0, "", ""),
+        freq("cpu1", 0.0, 0.0, 0.0, "", ""),
+        freq("cpu2", 1800.512, 0.0, 0.0, "", ""),
+        freq("cpu3", 3100.0, 0.0, 0.0, "", ""),
+    ]);
+    // aarch64 has neither cpufreq nor "cpu MHz"
+    assert_eq!(cpu::freq_with_context(&fixture("aarch64"), true).unwrap(), vec![
+        freq("cpu0", 0.0, 0.0, 0.0, "", ""),
+        freq("cpu1", 0.0, 0.0, 0.0, "", ""),
+        freq("cpu2", 0.0, 0.0, 0.0, "", ""),
+    ]);
+}
+
+#[test]
+fn stats() {
+    assert_eq!(cpu::stats_with_context(&fixture("x86_64")).unwrap(), StatsStat {
+        ctx_switches: 551843012,
+        interrupts: 298766548,
+        soft_interrupts: 122904365,
+        syscalls: 0,
+    });
+}
+
+#[test]
+fn interrupts() {
+    let irq = |irq: &str, per_cpu: Vec<u64>, description: &str| InterruptStat {
+        irq: irq.to_string(),
+        per_cpu,
+        description: description.to_string(),
+    };
+
+    assert_eq!(cpu::interrupts_with_context(&fixture("x86_64")).unwrap(), vec![
+        irq("0", vec![35, 0, 0, 0], "IO-APIC 2-edge timer"),
+        irq("1", vec![0, 9, 0, 0], "IO-APIC 1-edge i8042"),
+        irq("8", vec![0, 0, 1, 0], "IO-APIC 8-edge rtc0"),
+        irq("24", vec![4021735, 0, 0, 118373], "PCI-MSI 65536-edge nvme0q0"),
+        irq("25", vec![0, 7420113, 0, 0], "PCI-MSI 1572864-edge ens5-Tx-Rx-0"),
+        irq("NMI", vec![0, 0, 0, 0], "Non-maskable interrupts"),
+        irq("LOC", vec![72104882, 71920154, 71877011, 71902345], "Local timer interrupts"),
+        irq("TLB", vec![120443, 121007, 119884, 120911], "TLB shootdowns"),
+        irq("ERR", vec![0, 0, 0, 0], ""),
+        irq("MIS", vec![0, 0, 0, 0], ""),
+    ]);
+
+    // cpu1 is offline and missing from the header
+    assert_eq!(cpu::interrupts_with_context(&fixture("aarch64")).unwrap(), vec![
+        irq("11", vec![5321400, 0, 5298371], "GICv3 27 Level arch_timer"),
+        irq("14", vec![0, 0, 0], "GICv3 37 Level ttyAMA0"),
+        irq("IPI0", vec![1210, 0, 1098], "Rescheduling interrupts"),
+        irq("Err", vec![0, 0, 0], ""),
+    ]);
+}
+
+#[test]
+fn soft_interrupts() {
+    let softirqs = cpu::soft_interrupts_with_context(&fixture("x86_64")).unwrap();
+    assert_eq!(softirqs.len(), 10);
+    assert_eq!(softirqs[3], SoftInterruptStat {
+        name: "NET_RX".to_string(),
+        per_cpu: vec![101125, 39471800, 100020, 241175],
+    });
+    assert_eq!(softirqs[3].total(), 39914120);
+}
+
+#[test]
+fn topology_x86_64() {
+    let cache = |level: u32, cache_type: &str, size: u64, ways: u32, shared_cpus: &str| CacheStat {
+        level,
+        cache_type: cache_type.to_string(),
+        size,
+        line_size: 64,
+        ways_of_associativity: ways,
+        shared_cpus: set(shared_cpus),
+    };
+
+    assert_eq!(cpu::topology_with_context(&fixture("x86_64")).unwrap(), TopologyStat {
+        packages: vec![PackageStat {
+            id: 0,
+            dies: vec![DieStat {
+                id: 0,
+                cores: vec![
+                    CoreStat { id: 0, cpus: set("0,2") },
+                    CoreStat { id: 1, cpus: set("1,3") },
+                ],
+            }],
+        }],
+        numa_nodes: vec![NumaNodeStat { id: 0, cpus: set("0,1,2,3") }],
+        caches: vec![
+            cache(1, "Data", 48 << 10, 12, "0,2"),
+            cache(1, "Data", 48 << 10, 12, "1,3"),
+            cache(1, "Instruction", 32 << 10, 8, "0,2"),
+            cache(1, "Instruction", 32 << 10, 8, "1,3"),
+            cache(2, "Unified", 1280 << 10, 20, "0,2"),
+            cache(2, "Unified", 1280 << 10, 20, "1,3"),
+            cache(3, "Unified", 36608 << 10, 11, "0,1,2,3"),
+        ],
+    });
+}
+
+#[test]
+fn topology_kvm() {
+    let cache = |level: u32, cache_type: &str, size: u64, ways: u32| CacheStat {
+        level,
+        cache_type: cache_type.to_string(),
+        size,
+        line_size: 64,
+        ways_of_associativity: ways,
+        shared_cpus: set("0"),
+    };
+
+    assert_eq!(cpu::topology_with_context(&fixture("kvm")).unwrap(), TopologyStat {
+        packages: vec![PackageStat { id: 0, dies: vec![DieStat { id: 0, cores: vec![CoreStat { id: 0, cpus: set("0") }] }] }],
+        numa_nodes: vec![NumaNodeStat { id: 0, cpus: set("0") }],
+        caches: vec![
+            cache(1, "Data", 48 << 10, 12),
+            cache(1, "Instruction", 32 << 10, 8),
+            cache(2, "Unified", 2048 << 10, 16),
+            cache(3, "Unified", 107520 << 10, 15),
+        ],
+    });
+}
+
+#[test]
+fn topology_two_sockets_without_caches() {
+    assert_eq!(cpu::topology_with_context(&fixture("aarch64")).unwrap(), TopologyStat {
+        packages: vec![
+            PackageStat { id: 0, dies: vec![DieStat { id: 0, cores: vec![CoreStat { id: 0, cpus: set("0") }] }] },
+            PackageStat { id: 1, dies: vec![DieStat { id: 0, cores: vec![CoreStat { id: 2, cpus: set("2") }] }] },
+        ],
+        numa_nodes: vec![
+            NumaNodeStat { id: 0, cpus: set("0") },
+            NumaNodeStat { id: 1, cpus: set("2") },
+        ],
+        caches: vec![],
+    });
+    assert_eq!(cpu::topology_with_context(&fixture("container")).unwrap(), TopologyStat::default());
+}
+
+#[test]
+fn cpu_sets() {
+    let ctx = fixture("x86_64");
+    for (kind, expected) in [
+        (CpuSetKind::Online, "0-3"),
+        (CpuSetKind::Offline, "4-7"),
+        (CpuSetKind::Possible, "0-7"),
+        (CpuSetKind::Present, "0-7"),
+        // booted without isolcpus= nor nohz_full=
+        (CpuSetKind::Isolated, ""),
+        (CpuSetKind::NohzFull, ""),
+    ] {
+        assert_eq!(cpu::cpu_set_with_context(&ctx, kind).unwrap(), set(expected), "{:?}", kind);
+    }
+
+    // booted with isolcpus=3
+    let ctx = fixture("offline");
+    assert_eq!(cpu::cpu_set_with_context(&ctx, CpuSetKind::Isolated).unwrap(), set("3"));
+
+    // the aarch64 kernel is built without NO_HZ_FULL, so has no nohz_full
+    let ctx = fixture("aarch64");
+    assert_eq!(cpu::cpu_set_with_context(&ctx, CpuSetKind::Online).unwrap(), set("0,2"));
+    assert_eq!(cpu::cpu_set_with_context(&ctx, CpuSetKind::Offline).unwrap(), set("1"));
+    assert_eq!(cpu::cpu_set_with_context(&ctx, CpuSetKind::Isolated).unwrap(), CpuSet::new());
+    assert_eq!(cpu::cpu_set_with_context(&ctx, CpuSetKind::NohzFull).unwrap(), CpuSet::new());
+
+    // without sysfs
+    let ctx = fixture("container");
+    assert!(matches!(cpu::cpu_set_with_context(&ctx, CpuSetKind::Online), Err(rspsutil::Error::Io { .. })));
+}
+
+#[test]
+fn cpu_set_format() {
+    for (input, formatted) in [
+        ("0-3,8-11", "0-3,8-11"),
+        ("0,1,2,5", "0-2,5"),
+        ("7,3, 4-5", "3-5,7"),
+        ("", ""),
+        ("\n", ""),
+    ] {
+        assert_eq!(input.parse::<CpuSet>().unwrap().to_string(), formatted, "{:?}", input);
+    }
+
+    assert!("3-1".parse::<CpuSet>().is_err());
+    assert!("0-".parse::<CpuSet>().is_err());
+    assert!("a".parse::<CpuSet>().is_err());
+    assert_eq!(set("0-3,8-11").len(), 8);
+    assert!(set("0-3,8-11").contains(9));
+    assert_eq!(set("0-7").difference(&set("2-3")), set("0-1,4-7"));
+}
+
+#[test]
+fn effective_counts() {
+    // cgroup v2: the pod's quota of half a CPU applies to the container below it
+    let counts = cpu::effective_counts_with_context(&fixture("container")).unwrap();
+    assert!(counts.affinity >= 1);
+    assert_eq!(counts.cpuset, Some(2));
+    assert_eq!(counts.quota, Some(0.5));
+    assert_eq!(counts.effective, 1);
+
+    // cgroup v1 without a quota
+    let counts = cpu::effective_counts_with_context(&fixture("x86_64")).unwrap();
+    assert_eq!(counts.cpuset, Some(4));
+    assert_eq!(counts.quota, None);
+    assert_eq!(counts.effective, counts.affinity.min(4));
+
+    // no cgroup information at all
+    let counts = cpu::effective_counts_with_context(&fixture("power")).unwrap();
+    assert_eq!(counts, EffectiveCountsStat { affinity: counts.affinity, cpuset: None, quota: None, effective: counts.affinity });
+}
+
+#[test]
+fn vulnerabilities() {
+    let vuln = |name: &str, status, mitigation: &str, description: &str| VulnerabilityStat {
+        name: name.to_string(),
+        status,
+        mitigation: mitigation.to_string(),
+        description: description.to_string(),
+    };
+
+    assert_eq!(cpu::vulnerabilities_with_context(&fixture("x86_64")).unwrap(), vec![
+        vuln("gather_data_sampling", VulnerabilityStatus::Unknown, "", "Unknown: Dependent on hypervisor status"),
+        vuln("itlb_multihit", VulnerabilityStatus::Mitigated, "VMX disabled", "KVM: Mitigation: VMX disabled"),
+        vuln("l1tf", VulnerabilityStatus::Mitigated, "PTE Inversion; VMX: flush not necessary, SMT disabled",
+             "Mitigation: PTE Inversion; VMX: flush not necessary, SMT disabled"),
+        vuln("mds", VulnerabilityStatus::Vulnerable, "",
+             "Vulnerable: Clear CPU buffers attempted, no microcode; SMT Host state unknown"),
+        vuln("meltdown", VulnerabilityStatus::Mitigated, "PTI", "Mitigation: PTI"),
+        vuln("mmio_stale_data", VulnerabilityStatus::Vulnerable, "", "Vulnerable; SMT vulnerable"),
+        vuln("retbleed", VulnerabilityStatus::Vulnerable, "", "Vulnerable"),
+        vuln("spectre_v1", VulnerabilityStatus::Mitigated, "usercopy/swapgs barriers and __user pointer sanitization",
+             "Mitigation: usercopy/swapgs barriers and __user pointer sanitization"),
+        vuln("spectre_v2", VulnerabilityStatus::Vulnerable, "", "Vulnerable, IBPB: disabled, STIBP: disabled"),
+        vuln("srbds", VulnerabilityStatus::NotAffected, "", "Not affected"),
+    ]);
+
+    let kvm = cpu::vulnerabilities_with_context(&fixture("kvm")).unwrap();
+    assert_eq!(kvm.len(), 19);
+    assert_eq!(kvm.iter().filter(|x| x.status == VulnerabilityStatus::NotAffected).count(), 15);
+    assert_eq!(kvm[14], vuln("spectre_v2", VulnerabilityStatus::Mitigated,
+        "Enhanced / Automatic IBRS; IBPB: conditional; PBRSB-eIBRS: SW sequence; BHI: Vulnerable",
+        "Mitigation: Enhanced / Automatic IBRS; IBPB: conditional; PBRSB-eIBRS: SW sequence; BHI: Vulnerable"));
+    assert_eq!(kvm[17], vuln("tsx_async_abort", VulnerabilityStatus::Mitigated, "TSX disabled", "Mitigation: TSX disabled"));
+
+    let aarch64 = cpu::vulnerabilities_with_context(&fixture("aarch64")).unwrap();
+    assert_eq!(aarch64.len(), 5);
+    assert_eq!(aarch64[4], vuln("spectre_v2", VulnerabilityStatus::Mitigated, "CSV2, BHB", "Mitigation: CSV2, BHB"));
+
+    // no vulnerabilities directory, as on kernels before 4.15
+    assert_eq!(cpu::vulnerabilities_with_context(&fixture("s390x")).unwrap(), vec![]);
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn cpuid_infos() {
+    // CPUID describes the machine running the tests, so only check what
+    // every x86_64 CPU has
+    let infos = cpu::cpuid_infos().unwrap();
+    assert!(!infos.is_empty());
+    for info in infos {
+        assert!(info.cpu >= 0);
+        assert_eq!(info.vendor_id.len(), 12, "{:?}", info.vendor_id);
+        assert!(info.family.parse::<u32>().unwrap() > 0);
+        assert!(info.model.parse::<u32>().is_ok());
+        for flag in ["fpu", "tsc", "cx8", "cmov", "sse", "sse2", "lm"] {
+            assert!(info.flags.contains_name(flag), "{} missing from {}", flag, info.flags);
+        }
+    }
+}
+
+#[test]
+fn cpu_flags() {
+    let infos = cpu::infos_with_context(&fixture("x86_64")).unwrap();
+    let flags = &infos[0].flags;
+    assert!(flags.contains(CpuFlag::Avx512f));
+    assert!(flags.contains(CpuFlag::Aes));
+    assert!(!flags.contains(CpuFlag::Sve));
+    assert!(flags.contains_name("avx2"));
+    assert!(flags.contains_name("xtopology"));
+    assert!(!flags.contains_name("avx512_fp16"));
+    assert_eq!(flags.len(), strings(X86_FLAGS).len());
+    assert!(flags.unknown().all(|x| CpuFlag::from_name(x).is_none()));
+    assert!(!flags.unknown().any(|x| x == "constant_tsc"));
+    assert!(flags.unknown().any(|x| x == "rep_good"));
+
+    let infos = cpu::infos_with_context(&fixture("aarch64")).unwrap();
+    assert_eq!(infos[0].flags.known().collect::<Vec<_>>(), vec![
+        CpuFlag::Aes, CpuFlag::Cpuid, CpuFlag::Fp, CpuFlag::Asimd, CpuFlag::Evtstrm, CpuFlag::Pmull, CpuFlag::Sha1,
+        CpuFlag::Sha2, CpuFlag::Crc32, CpuFlag::Atomics, CpuFlag::Fphp, CpuFlag::Asimdhp, CpuFlag::Asimdrdm,
+        CpuFlag::Lrcpc, CpuFlag::Dcpop, CpuFlag::Asimddp, CpuFlag::Ssbs,
+    ]);
+    assert_eq!(infos[0].flags.unknown().count(), 0);
+
+    // separators are not flags
+    let flags = "fp,  asimd\tsve ".parse::<CpuFlags>().unwrap();
+    assert_eq!(flags.len(), 3);
+    assert_eq!(flags.to_string(), "fp asimd sve");
+    assert_eq!(flags, [CpuFlag::Sve, CpuFlag::Asimd, CpuFlag::Fp].into_iter().collect());
+}
+
+#[test]
+fn sched_stats() {
+    let domain = |level: u32, name: &str, cpus: &str, lb: [u64; 3], alb: [u64; 3], ttwu: [u64; 3]| SchedDomainStat {
+        level,
+        name: name.to_string(),
+        cpus: set(cpus),
+        lb_count: lb[0],
+        lb_balanced: lb[1],
+        lb_failed: lb[2],
+        alb_count: alb[0],
+        alb_failed: alb[1],
+        alb_pushed: alb[2],
+        ttwu_wake_remote: ttwu[0],
+        ttwu_move_affine: ttwu[1],
+        ttwu_move_balance: ttwu[2],
+    };
+    let cpu = |cpu: u32, v: [u64; 7], domains: Vec<SchedDomainStat>| SchedStat {
+        cpu,
+        sched_count: v[0],
+        sched_goidle: v[1],
+        ttwu_count: v[2],
+        ttwu_local: v[3],
+        run_time: v[4],
+        wait_time: v[5],
+        timeslices: v[6],
+        domains,
+    };
+
+    // version 15: an SMT domain per core, then the package
+    let x86 = cpu::sched_stats_with_context(&fixture("x86_64")).unwrap();
+    assert_eq!(x86, vec![
+        cpu(0, [29516625, 13000006, 16554402, 10427000, 3451802368041, 126191951511, 16516224], vec![
+            domain(0, "", "0,2", [419002, 323370, 74709], [17, 5, 12], [118483, 16491, 0]),
+            domain(1, "", "0-3", [880776, 664191, 189234], [3, 0, 3], [462107, 53692, 0]),
+        ]),
+        cpu(1, [28277753, 12612525, 14898236, 8458190, 3434666204532, 195478196289, 15662417], vec![
+            domain(0, "", "1,3", [352153, 272850, 57793], [4, 0, 4], [101102, 7683, 0]),
+            domain(1, "", "0-3", [973768, 780768, 167180], [8, 1, 7], [239062, 30257, 0]),
+        ]),
+        cpu(2, [30078549, 11692419, 14610557, 9880099, 3439114273144, 112221141488, 18385969], vec![
+            domain(0, "", "0,2", [340815, 249090, 65982], [23, 2, 21], [117116, 12802, 0]),
+            domain(1, "", "0-3", [738904, 568754, 140649], [8, 1, 7], [812500, 83266, 0]),
+        ]),
+        cpu(3, [28225203, 11156572, 15128402, 10358498, 3507515421671, 191961848191, 17068086], vec![
+            domain(0, "", "1,3", [313666, 238650, 55955], [12, 1, 11], [123045, 17957, 0]),
+            domain(1, "", "0-3", [830194, 609898, 176281], [4, 1, 3], [698306, 70250, 0]),
+        ]),
+    ]);
+
+    // the SMT domains are the cores of the topology
+    let topology = cpu::topology_with_context(&fixture("x86_64")).unwrap();
+    let cores = &topology.packages[0].dies[0].cores;
+    for stat in &x86 {
+        let core = cores.iter().find(|x| x.cpus.contains(stat.cpu)).unwrap();
+        assert_eq!(stat.domains[0].cpus, core.cpus, "cpu{}", stat.cpu);
+    }
+
+    // version 17 names domains and has more load balancing fields. One CPU
+    // per node leaves only the NUMA domain, without the offline cpu1.
+    let arm = cpu::sched_stats_with_context(&fixture("aarch64")).unwrap();
+    assert_eq!(arm, vec![
+        cpu(0, [506869, 221969, 252069, 170613, 25953946422, 1045765217, 284854], vec![
+            domain(0, "NUMA", "0,2", [84897, 68491, 14412], [0, 0, 0], [49051, 6834, 0]),
+        ]),
+        cpu(2, [505152, 216012, 256227, 147029, 19688016700, 646444994, 289110], vec![
+            domain(0, "NUMA", "0,2", [100919, 73463, 22348], [27, 3, 24], [55812, 10147, 0]),
+        ]),
+    ]);
+    let topology = cpu::topology_with_context(&fixture("aarch64")).unwrap();
+    let nodes = topology.numa_nodes.iter().fold(CpuSet::new(), |acc, x| acc.union(&x.cpus));
+    assert_eq!(arm[0].domains[0].cpus, nodes);
+}
@@ -0,0 +1,98 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::fixture;
+use rspsutil::cpu::{self, TimesStat};
+
+fn percents(cpu: &str, v: [f64; 10]) -> TimesStat {
+    TimesStat {
+        cpu: cpu.to_string(),
+        user: v[0],
+        nice: v[1],
+        system: v[2],
+        idle: v[3],
+        io_wait: v[4],
+        irq: v[5],
+        soft_irq: v[6],
+        steal: v[7],
+        guest: v[8],
+        guest_nice: v[9],
+    }
+}
+
+fn fields(t: &TimesStat) -> [f64; 10] {
+    [t.user, t.nice, t.system, t.idle, t.io_wait, t.irq, t.soft_irq, t.steal, t.guest, t.guest_nice]
+}
+
+fn assert_times_close(actual: Vec<TimesStat>, expected: &[TimesStat]) {
+    assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+    for (a, e) in actual.iter().zip(expected) {
+        assert_eq!(a.cpu, e.cpu);
+        assert_close(fields(a).to_vec(), &fields(e));
+    }
+}
+
+fn assert_busy_close(actual: Vec<(String, f64)>, expected: &[(&str, f64)]) {
+    assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+    for ((cpu, a), (name, e)) in actual.iter().zip(expected) {
+        assert_eq!(cpu, name);
+        assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+}
+
+fn assert_close(actual: Vec<f64>, expected: &[f64]) {
+    assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+    }
+}
+
+// percent keeps its last sample in process-wide state, so every step runs
+// in a single test.
+#[test]
+fn percent_against_previous_sample() {
+    let t1 = fixture("percent/t1");
+    let t2 = fixture("percent/t2");
+
+    // nothing to compare against yet
+    assert_busy_close(cpu::percent_with_context(&t1, Duration::ZERO, false).unwrap(), &[("cpu-total", 0.0)]);
+    // guest is part of user and must not be counted twice
+    assert_busy_close(cpu::percent_with_context(&t2, Duration::ZERO, false).unwrap(), &[("cpu-total", 40.0)]);
+
+    assert_busy_close(cpu::percent_with_context(&t1, Duration::ZERO, true).unwrap(), &[
+        ("cpu0", 0.0), ("cpu1", 0.0), ("cpu2", 0.0),
+    ]);
+    // cpu1's user time goes backwards and counts as 0, cpu2 was unplugged
+    // and cpu3 has no previous sample
+    assert_busy_close(cpu::percent_with_context(&t2, Duration::ZERO, true).unwrap(), &[
+        ("cpu0", 30.0), ("cpu1", 40.0), ("cpu3", 0.0),
+    ]);
+
+    // the fixture does not change while sleeping
+    assert_busy_close(cpu::percent_with_context(&t2, Duration::from_millis(10), true).unwrap(), &[
+        ("cpu0", 0.0), ("cpu1", 0.0), ("cpu3", 0.0),
+    ]);
+}
+
+#[test]
+fn times_percent_against_previous_sample() {
+    let t1 = fixture("percent/t1");
+    let t2 = fixture("percent/t2");
+
+    assert_times_close(cpu::times_percent_with_context(&t1, Duration::ZERO, false).unwrap(), &[
+        percents("cpu-total", [0.0; 10]),
+    ]);
+    assert_times_close(cpu::times_percent_with_context(&t2, Duration::ZERO, false).unwrap(), &[
+        percents("cpu-total", [30.0, 0.0, 10.0, 50.0, 10.0, 0.0, 0.0, 0.0, 20.0, 0.0]),
+    ]);
+
+    cpu::times_percent_with_context(&t1, Duration::ZERO, true).unwrap();
+    assert_times_close(cpu::times_percent_with_context(&t2, Duration::ZERO, true).unwrap(), &[
+        percents("cpu0", [20.0, 0.0, 10.0, 70.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        percents("cpu1", [0.0, 0.0, 40.0, 40.0, 20.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        percents("cpu3", [0.0; 10]),
+    ]);
+}
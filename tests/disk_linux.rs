@@ -0,0 +1,329 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{fixture, strings};
+use rspsutil::disk::{self, BlockDeviceKind, BlockDeviceStat, IOCountersStat, IORateStat, PartitionStat};
+
+fn partition(device: &str, mountpoint: &str, fstype: &str, opts: &str) -> PartitionStat {
+    PartitionStat {
+        device: device.to_string(),
+        mountpoint: mountpoint.to_string(),
+        fstype: fstype.to_string(),
+        opts: opts.split(',').map(String::from).collect(),
+    }
+}
+
+#[test]
+fn partitions_from_mountinfo() {
+    let ctx = fixture("x86_64");
+    let expected = vec![
+        // /dev/root resolved through /sys/dev/block/259:2
+        partition("/dev/nvme0n1p2", "/", "ext4", "rw,relatime,discard,errors=remount-ro"),
+        partition("/dev/nvme0n1p1", "/boot/efi", "vfat",
+                  "rw,relatime,fmask=0077,dmask=0077,codepage=437,iocharset=iso8859-1,shortname=mixed,errors=remount-ro"),
+        partition("/dev/dm-0", "/var/lib/data", "xfs", "rw,noatime,attr2,inode64,logbufs=8,logbsize=32k,noquota"),
+        partition("/dev/nvme0n1p3", "/mnt/backup disk", "ext4", "rw,relatime"),
+        partition("tank", "/tank", "zfs", "rw,noatime,xattr,noacl"),
+    ];
+    assert_eq!(disk::partitions_with_context(&ctx, false).unwrap(), expected);
+
+    let all = disk::partitions_with_context(&ctx, true).unwrap();
+    assert_eq!(all.len(), 10);
+    assert_eq!(all[0], partition("sysfs", "/sys", "sysfs", "rw,nosuid,nodev,noexec,relatime"));
+    assert_eq!(all.iter().map(|x| x.fstype.as_str()).collect::<Vec<_>>(), strings(
+        "sysfs proc devtmpfs ext4 cgroup2 vfat xfs ext4 tmpfs zfs"
+    ));
+}
+
+#[test]
+fn partitions_kvm() {
+    assert_eq!(disk::partitions_with_context(&fixture("kvm"), false).unwrap(), vec![
+        partition("/dev/vda", "/", "ext4", "rw,relatime,discard,resv_strict,resuid=65534,resgid=65534"),
+    ]);
+}
+
+#[test]
+fn partitions_from_mounts() {
+    // no mountinfo, falls back to /proc/mounts
+    assert_eq!(disk::partitions_with_context(&fixture("container"), false).unwrap(), vec![
+        partition("/dev/nvme0n1p2", "/etc/hosts", "ext4", "rw,relatime,discard,errors=remount-ro"),
+    ]);
+
+    // and then to /etc/mtab
+    assert_eq!(disk::partitions_with_context(&fixture("s390x"), false).unwrap(), vec![
+        partition("/dev/dasda1", "/", "ext4", "rw,relatime"),
+    ]);
+}
+
+#[test]
+fn io_counters() {
+    let ctx = fixture("x86_64");
+    let all = disk::io_counters_with_context(&ctx, &[]).unwrap();
+    assert_eq!(all.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), strings(
+        "loop0 nvme0n1 nvme0n1p1 nvme0n1p2 nvme0n1p3 dm-0"
+    ));
+    assert_eq!(all[1], IOCountersStat {
+        read_count: 281452,
+        merged_read_count: 61735,
+        write_count: 1632718,
+        merged_write_count: 905873,
+        // 512 byte sectors
+        read_bytes: 19871302 * 512,
+        write_bytes: 78564210 * 512,
+        read_time: 58473,
+        write_time: 1437692,
+        iops_in_progress: 0,
+        io_time: 1021896,
+        weighted_io: 1562814,
+        discard_count: 48213,
+        merged_discard_count: 0,
+        discard_bytes: 98631424 * 512,
+        discard_time: 3927,
+        flush_count: 110247,
+        flush_time: 62722,
+        name: "nvme0n1".to_string(),
+        serial_number: "Samsung_SSD_970_EVO_Plus_1TB_S4EWNX0R123456".to_string(),
+        label: "".to_string(),
+    });
+
+    let some = disk::io_counters_with_context(&ctx, &["dm-0", "nvme0n1p1", "sdz"]).unwrap();
+    assert_eq!(some.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), strings("nvme0n1p1 dm-0"));
+    assert_eq!(some[1].iops_in_progress, 2);
+    assert_eq!(some[1].write_bytes, 480928 * 512);
+
+    // kernels before 4.18 have neither discard nor flush fields
+    let old = disk::io_counters_with_context(&fixture("s390x"), &["dasda"]).unwrap();
+    assert_eq!(old.len(), 1);
+    assert_eq!(old[0].weighted_io, 221680);
+    assert_eq!((old[0].discard_count, old[0].flush_count), (0, 0));
+}
+
+#[test]
+fn io_counters_kvm() {
+    let all = disk::io_counters_with_context(&fixture("kvm"), &[]).unwrap();
+    assert_eq!(all.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), strings("loop0 vda vdb zram0"));
+    assert_eq!(all[1], IOCountersStat {
+        read_count: 5717,
+        merged_read_count: 3800,
+        read_bytes: 2076290 * 512,
+        read_time: 8523,
+        write_count: 22018,
+        merged_write_count: 18616,
+        write_bytes: 10519592 * 512,
+        write_time: 26952,
+        iops_in_progress: 0,
+        io_time: 9556,
+        weighted_io: 38898,
+        discard_count: 17820,
+        merged_discard_count: 0,
+        discard_bytes: 9139768 * 512,
+        discard_time: 3419,
+        flush_count: 114,
+        flush_time: 2,
+        name: "vda".to_string(),
+        serial_number: "".to_string(),
+        label: "".to_string(),
+    });
+}
+
+#[test]
+fn serial_number_and_label() {
+    let ctx = fixture("x86_64");
+    // from the udev database
+    assert_eq!(disk::serial_number_with_context(&ctx, "/dev/nvme0n1").unwrap(),
+               "Samsung_SSD_970_EVO_Plus_1TB_S4EWNX0R123456");
+    assert_eq!(disk::label_with_context(&ctx, "nvme0n1p2").unwrap(), "root");
+    // from /dev/disk/by-label
+    assert_eq!(disk::label_with_context(&ctx, "nvme0n1p3").unwrap(), "backup disk");
+    assert_eq!(disk::label_with_context(&ctx, "dm-0").unwrap(), "data");
+    assert_eq!(disk::label_with_context(&ctx, "nvme0n1").unwrap(), "");
+    assert_eq!(disk::serial_number_with_context(&ctx, "dm-0").unwrap(), "");
+
+    // without udev, from sysfs
+    let ctx = ctx.with_run("/nonexistent");
+    assert_eq!(disk::serial_number_with_context(&ctx, "nvme0n1").unwrap(), "S4EWNX0R123456");
+
+    let all = disk::io_counters_with_context(&fixture("x86_64"), &["nvme0n1p2", "nvme0n1p3"]).unwrap();
+    assert_eq!(all.iter().map(|x| (x.serial_number.as_str(), x.label.as_str())).collect::<Vec<_>>(), vec![
+        ("Samsung_SSD_970_EVO_Plus_1TB_S4EWNX0R123456", "root"),
+        ("", "backup disk"),
+    ]);
+}
+
+#[test]
+fn io_rates() {
+    let t1 = disk::io_counters_with_context(&fixture("x86_64"), &["nvme0n1", "nvme0n1p1", "dm-0"]).unwrap();
+    let mut t2 = t1.clone();
+
+    // nvme0n1: 2s of reads and writes
+    let d = &mut t2[0];
+    d.read_count += 400;
+    d.merged_read_count += 20;
+    d.read_bytes += 8 * 1024 * 1024;
+    d.read_time += 200;
+    d.write_count += 100;
+    d.write_bytes += 2 * 1024 * 1024;
+    d.write_time += 300;
+    d.discard_count += 2;
+    d.discard_bytes += 1024 * 1024;
+    d.discard_time += 2;
+    d.flush_count += 10;
+    d.flush_time += 5;
+    d.io_time += 500;
+    d.weighted_io += 1000;
+
+    // nvme0n1p1: removed, a new device took its name
+    t2[1].read_count = 10;
+    t2[1].read_time = 4;
+
+    // dm-0: its 32-bit time counters wrapped, and it went away
+    let mut dm = t2.remove(2);
+    dm.io_time = 100;
+    let mut before = t1[2].clone();
+    before.io_time = u32::MAX as u64 - 99;
+
+    let rates = disk::io_rates_between(&t1, &t2, Duration::from_secs(2));
+    assert_eq!(rates, vec![
+        IORateStat {
+            name: "nvme0n1".to_string(),
+            read_per_sec: 200.0,
+            write_per_sec: 50.0,
+            discard_per_sec: 1.0,
+            flush_per_sec: 5.0,
+            merged_read_per_sec: 10.0,
+            merged_write_per_sec: 0.0,
+            read_mb_per_sec: 4.0,
+            write_mb_per_sec: 1.0,
+            discard_mb_per_sec: 0.5,
+            read_await: 0.5,
+            write_await: 3.0,
+            discard_await: 1.0,
+            flush_await: 0.5,
+            avg_await: 1.0,
+            avg_queue_size: 0.5,
+            util_percent: 25.0,
+        },
+        IORateStat {
+            name: "nvme0n1p1".to_string(),
+            read_per_sec: 5.0,
+            read_await: 0.4,
+            avg_await: 0.4,
+            ..Default::default()
+        },
+    ]);
+
+    let rates = disk::io_rates_between(&[before], &[dm], Duration::from_secs(1));
+    assert_eq!(rates[0].util_percent, 20.0);
+
+    // request and sector counts of a 32-bit kernel wrap at 2^32
+    let (mut before, mut after) = (t1[0].clone(), t1[0].clone());
+    before.read_count = u32::MAX as u64 - 9;
+    after.read_count = 10;
+    before.read_bytes = (u32::MAX as u64 - 1023) * 512;
+    after.read_bytes = 1024 * 512;
+    let rates = disk::io_rates_between(&[before], &[after], Duration::from_secs(1));
+    assert_eq!((rates[0].read_per_sec, rates[0].read_mb_per_sec), (20.0, 1.0));
+
+    // those of a 64-bit kernel at 2^64, so going back from below 2^63 is a reset
+    let (mut before, mut after) = (t1[0].clone(), t1[0].clone());
+    before.read_count = u64::MAX - 9;
+    after.read_count = 10;
+    before.write_count = 5_000_000_000;
+    after.write_count = 10;
+    let rates = disk::io_rates_between(&[before], &[after], Duration::from_secs(1));
+    assert_eq!((rates[0].read_per_sec, rates[0].write_per_sec), (20.0, 10.0));
+
+    // nothing happens between two reads of the same snapshot
+    let ctx = fixture("x86_64");
+    let rates = disk::io_rates_with_context(&ctx, Duration::ZERO, &[]).unwrap();
+    assert_eq!(rates.len(), 6);
+    assert!(rates.iter().all(|x| x.read_per_sec == 0.0 && x.util_percent == 0.0));
+}
+
+#[test]
+fn block_devices() {
+    let devices = disk::block_devices_with_context(&fixture("x86_64")).unwrap();
+    assert_eq!(devices.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), strings(
+        "dm-0 loop0 nvme0n1 nvme0n1p1 nvme0n1p2 nvme0n1p3 sda sda1"
+    ));
+
+    assert_eq!(devices[2], BlockDeviceStat {
+        name: "nvme0n1".to_string(),
+        major: 259,
+        minor: 0,
+        kind: BlockDeviceKind::Disk,
+        size: 1953525168 * 512,
+        logical_sector_size: 512,
+        physical_sector_size: 512,
+        rotational: false,
+        removable: false,
+        read_only: false,
+        model: "Samsung SSD 970 EVO Plus 1TB".to_string(),
+        vendor: "".to_string(),
+        wwn: "eui.002538b511b0c0d1".to_string(),
+        scheduler: "none".to_string(),
+        queue_depth: 0,
+        nr_requests: 1023,
+        parent: None,
+        children: strings("nvme0n1p1 nvme0n1p2 nvme0n1p3"),
+        slaves: vec![],
+        holders: vec![],
+    });
+
+    let sda = &devices[6];
+    assert_eq!((sda.model.as_str(), sda.vendor.as_str(), sda.wwn.as_str()),
+               ("ST4000DM004-2CV1", "ATA", "naa.5000c500a1b2c3d4"));
+    assert_eq!((sda.logical_sector_size, sda.physical_sector_size), (512, 4096));
+    assert_eq!((sda.rotational, sda.scheduler.as_str(), sda.queue_depth, sda.nr_requests), (true, "bfq", 32, 64));
+
+    // partitions inherit the queue of their disk
+    assert_eq!(devices[7], BlockDeviceStat {
+        name: "sda1".to_string(),
+        major: 8,
+        minor: 1,
+        kind: BlockDeviceKind::Partition,
+        size: 7814035456 * 512,
+        logical_sector_size: 512,
+        physical_sector_size: 4096,
+        rotational: true,
+        scheduler: "bfq".to_string(),
+        queue_depth: 32,
+        nr_requests: 64,
+        parent: Some("sda".to_string()),
+        holders: strings("dm-0"),
+        ..Default::default()
+    });
+
+    let dm = &devices[0];
+    assert_eq!((dm.kind, dm.slaves.clone(), dm.parent.clone()), (BlockDeviceKind::DeviceMapper, strings("sda1"), None));
+    assert_eq!((devices[1].kind, devices[1].read_only), (BlockDeviceKind::Loop, true));
+}
+
+#[test]
+fn block_devices_kvm() {
+    let devices = disk::block_devices_with_context(&fixture("kvm")).unwrap();
+    assert_eq!(devices.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), strings("loop0 vda vdb zram0"));
+    assert_eq!(devices[1], BlockDeviceStat {
+        name: "vda".to_string(),
+        major: 254,
+        minor: 0,
+        kind: BlockDeviceKind::Disk,
+        size: 536870912 * 512,
+        logical_sector_size: 512,
+        physical_sector_size: 4096,
+        rotational: true,
+        // the virtio vendor, as lsblk shows it
+        vendor: "0x1af4".to_string(),
+        scheduler: "mq-deadline".to_string(),
+        nr_requests: 256,
+        ..Default::default()
+    });
+    assert!(devices[2].read_only);
+
+    // zram has no request queue to schedule
+    let zram = &devices[3];
+    assert_eq!((zram.logical_sector_size, zram.scheduler.as_str(), zram.nr_requests), (4096, "", 0));
+}
@@ -0,0 +1,68 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::{fs, io};
+
+use common::fixture;
+use rspsutil::load::{self, AvgStat, MiscStat};
+use rspsutil::{Context, Error};
+
+#[test]
+fn avg() {
+    assert_eq!(load::avg_with_context(&fixture("x86_64")).unwrap(), AvgStat {
+        load1: 0.52,
+        load5: 0.58,
+        load15: 0.59,
+    });
+    assert_eq!(load::avg_with_context(&fixture("kvm")).unwrap(), AvgStat { load1: 0.1, load5: 0.12, load15: 0.14 });
+}
+
+#[test]
+fn avg_missing_loadavg() {
+    // the aarch64 fixture has no /proc/loadavg, getloadavg would report the
+    // load of this host rather than the fixture's
+    match load::avg_with_context(&fixture("aarch64")) {
+        Err(Error::Io { source, .. }) => assert_eq!(source.kind(), io::ErrorKind::NotFound),
+        other => panic!("expected a NotFound error, got {:?}", other),
+    }
+}
+
+#[test]
+fn misc() {
+    assert_eq!(load::misc_with_context(&fixture("x86_64")).unwrap(), MiscStat {
+        procs_total: 1043,
+        procs_created: 1328406,
+        procs_running: 2,
+        procs_blocked: 0,
+        ctxt: 551843012,
+    });
+    assert_eq!(load::misc_with_context(&fixture("kvm")).unwrap(), MiscStat {
+        procs_total: 71,
+        procs_created: 54690,
+        procs_running: 4,
+        procs_blocked: 0,
+        ctxt: 1485509,
+    });
+    assert_eq!(load::misc_with_context(&fixture("container")).unwrap(), MiscStat {
+        procs_total: 57,
+        procs_created: 4100,
+        procs_running: 3,
+        procs_blocked: 1,
+        ctxt: 2003004,
+    });
+}
+
+#[test]
+fn misc_unreadable_stat() {
+    // reading a directory fails on every line, which must end the read
+    // with an error rather than loop or yield empty lines
+    let root = std::env::temp_dir().join(format!("rspsutil-misc-{}", std::process::id()));
+    fs::create_dir_all(root.join("proc/stat")).unwrap();
+    let ret = load::misc_with_context(&Context::from_root(&root));
+    fs::remove_dir_all(&root).unwrap();
+    match ret {
+        Err(Error::Io { path, .. }) => assert_eq!(path, root.join("proc/stat")),
+        other => panic!("expected an I/O error, got {:?}", other),
+    }
+}
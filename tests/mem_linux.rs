@@ -0,0 +1,178 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::fs;
+
+use common::fixture;
+use rspsutil::mem::{self, SwapDevice, SwapMemoryStat, VirtualMemoryStat};
+use rspsutil::Context;
+
+const KB: u64 = 1024;
+
+fn page_size() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as u64 }
+}
+
+#[test]
+fn virtual_memory_x86_64() {
+    let ctx = fixture("x86_64");
+    let total = 16093560 * KB;
+    let free = 8012372 * KB;
+    let buffers = 314560 * KB;
+    // Cached includes SReclaimable
+    let cached = (4401536 + 402844) * KB;
+    let used = total - free - buffers - cached;
+
+    assert_eq!(mem::virtual_memory_with_context(&ctx).unwrap(), VirtualMemoryStat {
+        total,
+        available: 12881520 * KB,
+        used,
+        used_percent: used as f64 / total as f64 * 100.0,
+        free,
+        active: 3918044 * KB,
+        inactive: 3302612 * KB,
+        buffers,
+        cached,
+        dirty: 212 * KB,
+        shared: 51516 * KB,
+        slab: 640232 * KB,
+        sreclaimable: 402844 * KB,
+        sunreclaim: 237388 * KB,
+        page_tables: 28112 * KB,
+        commit_limit: 10143928 * KB,
+        committed_as: 7218356 * KB,
+        swap_total: 3145720 * KB,
+        swap_free: 2621432 * KB,
+        mapped: 612904 * KB,
+        vmalloc_total: 34359738367 * KB,
+        vmalloc_used: 58920 * KB,
+        huge_pages_total: 8,
+        huge_pages_free: 6,
+        huge_pages_rsvd: 1,
+        huge_page_size: 2048 * KB,
+        anon_huge_pages: 616448 * KB,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn virtual_memory_estimates_available_without_memavailable() {
+    let ctx = fixture("s390x");
+    let v = mem::virtual_memory_with_context(&ctx).unwrap();
+
+    // two zones with a low watermark of 1280 pages each
+    let watermark_low = 2 * 1280 * page_size();
+    let page_cache = (564224 + 552960) * KB;
+    let sreclaimable = 122880 * KB;
+    let expected = 2310240 * KB - watermark_low
+        + page_cache - std::cmp::min(page_cache / 2, watermark_low)
+        + sreclaimable - std::cmp::min(sreclaimable / 2, watermark_low);
+
+    assert_eq!(v.available, expected);
+    assert_eq!(v.cached, (1024000 + 122880) * KB);
+    assert_eq!(v.used, (4034304 - 2310240 - 102400 - 1024000 - 122880) * KB);
+}
+
+#[test]
+fn virtual_memory_kvm() {
+    let ctx = fixture("kvm");
+    let total = 6147400 * KB;
+    let free = 4132648 * KB;
+    let buffers = 61956 * KB;
+    let cached = (1642992 + 51696) * KB;
+    let used = total - free - buffers - cached;
+
+    assert_eq!(mem::virtual_memory_with_context(&ctx).unwrap(), VirtualMemoryStat {
+        total,
+        available: 5644420 * KB,
+        used,
+        used_percent: used as f64 / total as f64 * 100.0,
+        free,
+        active: 614976 * KB,
+        inactive: 1270152 * KB,
+        buffers,
+        cached,
+        dirty: 128 * KB,
+        shared: 9288 * KB,
+        slab: 71756 * KB,
+        sreclaimable: 51696 * KB,
+        sunreclaim: 20060 * KB,
+        page_tables: 2068 * KB,
+        commit_limit: 3073700 * KB,
+        committed_as: 336328 * KB,
+        mapped: 142052 * KB,
+        vmalloc_total: 34359738367 * KB,
+        vmalloc_used: 15864 * KB,
+        huge_page_size: 2048 * KB,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn virtual_memory_container() {
+    let ctx = fixture("container");
+    assert_eq!(mem::virtual_memory_with_context(&ctx).unwrap(), VirtualMemoryStat {
+        total: 2097152 * KB,
+        available: 1835008 * KB,
+        used: 262144 * KB,
+        used_percent: 12.5,
+        free: 1572864 * KB,
+        active: 196608 * KB,
+        inactive: 131072 * KB,
+        cached: 262144 * KB,
+        shared: 4096 * KB,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn swap_memory() {
+    let page = 4 * KB;
+    assert_eq!(mem::swap_memory_with_context(&fixture("x86_64")).unwrap(), SwapMemoryStat {
+        total: 3145720 * KB,
+        used: 524288 * KB,
+        free: 2621432 * KB,
+        used_percent: 524288.0 / 3145720.0 * 100.0,
+        sin: 20417 * page,
+        sout: 151633 * page,
+        pg_in: 18923416 * page,
+        pg_out: 30418772 * page,
+        pg_fault: 312905117 * page,
+        pg_maj_fault: 40312 * page,
+    });
+}
+
+#[test]
+fn swap_memory_without_swap() {
+    let page = 4 * KB;
+    assert_eq!(mem::swap_memory_with_context(&fixture("kvm")).unwrap(), SwapMemoryStat {
+        pg_in: 1038290 * page,
+        pg_out: 5259796 * page,
+        pg_fault: 24333822 * page,
+        pg_maj_fault: 1423 * page,
+        ..Default::default()
+    });
+    assert_eq!(mem::swap_devices_with_context(&fixture("kvm")).unwrap(), vec![]);
+}
+
+#[test]
+fn swap_devices() {
+    assert_eq!(mem::swap_devices_with_context(&fixture("x86_64")).unwrap(), vec![
+        SwapDevice { name: "/dev/nvme0n1p3".to_string(), used_bytes: 0, free_bytes: 2097148 * KB },
+        SwapDevice { name: "/swapfile".to_string(), used_bytes: 524288 * KB, free_bytes: (1048572 - 524288) * KB },
+    ]);
+    assert_eq!(mem::swap_devices_with_context(&fixture("container")).unwrap(), vec![]);
+}
+
+#[test]
+fn swap_devices_during_swapoff() {
+    // Used briefly exceeds Size while the device is being swapped off
+    let root = std::env::temp_dir().join(format!("rspsutil-swapoff-{}", std::process::id()));
+    fs::create_dir_all(root.join("proc")).unwrap();
+    fs::write(root.join("proc/swaps"), "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n\
+        /swapfile                               file\t\t1024\t\t2048\t\t-2\n").unwrap();
+    let ret = mem::swap_devices_with_context(&Context::from_root(&root));
+    fs::remove_dir_all(&root).unwrap();
+    assert_eq!(ret.unwrap(), vec![SwapDevice { name: "/swapfile".to_string(), used_bytes: 2048 * KB, free_bytes: 0 }]);
+}
@@ -0,0 +1,60 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{fixture, fixture_dir};
+use rspsutil::pressure::{self, PressureStat, Resource, StallStat};
+
+fn stall(avg10: f64, avg60: f64, avg300: f64, total: u64) -> Option<StallStat> {
+    Some(StallStat { avg10, avg60, avg300, total })
+}
+
+#[test]
+fn stat() {
+    let ctx = fixture("x86_64");
+    assert_eq!(pressure::stat_with_context(&ctx, Resource::Cpu).unwrap(), PressureStat {
+        some: stall(12.5, 8.31, 3.02, 184265021),
+        full: stall(0.0, 0.0, 0.0, 0),
+    });
+    assert_eq!(pressure::stat_with_context(&ctx, Resource::Memory).unwrap(), PressureStat {
+        some: stall(0.0, 0.12, 0.08, 1426381),
+        full: stall(0.0, 0.05, 0.03, 725433),
+    });
+    assert_eq!(pressure::stat_with_context(&ctx, Resource::Io).unwrap(), PressureStat {
+        some: stall(1.04, 0.88, 0.61, 39618573),
+        full: stall(0.52, 0.4, 0.27, 20178744),
+    });
+    // irq only has a full line
+    assert_eq!(pressure::stat_with_context(&ctx, Resource::Irq).unwrap(), PressureStat {
+        some: None,
+        full: stall(0.0, 0.01, 0.0, 81529),
+    });
+
+    // since Linux 5.13 cpu has a full line, always zero system-wide
+    assert_eq!(pressure::stat_with_context(&fixture("kvm"), Resource::Cpu).unwrap(), PressureStat {
+        some: stall(0.78, 1.72, 1.98, 97493091),
+        full: stall(0.0, 0.0, 0.0, 0),
+    });
+}
+
+#[test]
+fn stat_without_psi() {
+    assert!(matches!(
+        pressure::stat_with_context(&fixture("aarch64"), Resource::Cpu),
+        Err(rspsutil::Error::Io { .. })
+    ));
+}
+
+#[test]
+fn cgroup_stat() {
+    let dir = fixture_dir("container").join("sys/fs/cgroup/kubepods/pod1/ctr");
+    assert_eq!(pressure::cgroup_stat(&dir, Resource::Cpu).unwrap(), PressureStat {
+        some: stall(48.2, 41.75, 30.1, 9120455870),
+        full: stall(46.01, 39.64, 28.55, 8640120534),
+    });
+    assert_eq!(pressure::cgroup_stat(&dir, Resource::Memory).unwrap(), PressureStat {
+        some: stall(0.0, 0.0, 0.0, 0),
+        full: stall(0.0, 0.0, 0.0, 0),
+    });
+    assert!(pressure::cgroup_stat(&dir, Resource::Io).is_err());
+}